@@ -0,0 +1,624 @@
+//
+// 6502 Assembler
+//
+// Small two-pass assembler for tests and ROM patches. Opcodes come from
+// LOOK_UP, so every official and unofficial instruction the CPU knows can
+// be assembled.
+//
+//	label:  LDA #$20	; comment
+//	        BNE label
+//	NAME = expr
+//	.org  $C000
+//	.byte 1, $02, 'c', "text", <label, >label
+//	.word label, *+2
+//
+// Numbers are $hex, %binary, 0x-hex, decimal or 'c'. Expressions support
+// + - * / % & | ^ << >> with the usual precedence, parentheses, unary -
+// and ~, < (low byte), > (high byte) and * for the current address.
+// Operands below $100 that are already known in the first pass use zero
+// page addressing when the instruction has it.
+
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::bus::Bus;
+use crate::cpu::{AddressingMode, LOOK_UP};
+use crate::disasm::Labels;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+	pub line: usize,
+	pub message: String,
+}
+
+impl fmt::Display for AsmError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for AsmError {}
+
+// Contiguous run of output starting at addr, one per .org
+pub struct Chunk {
+	pub addr: u16,
+	pub bytes: Vec<u8>,
+}
+
+pub struct Assembly {
+	pub chunks: Vec<Chunk>,
+	pub symbols: HashMap<String, u16>,
+}
+
+impl Assembly {
+
+	// Writes every chunk through the bus
+	pub fn load(&self, bus: &mut dyn Bus) {
+		for chunk in &self.chunks {
+			for (i, &byte) in chunk.bytes.iter().enumerate() {
+				bus.write(chunk.addr.wrapping_add(i as u16), byte);
+			}
+		}
+	}
+
+	// Address of the first byte emitted
+	pub fn origin(&self) -> u16 {
+		self.chunks.first().map_or(0, |chunk| chunk.addr)
+	}
+
+	// All output concatenated, for single-chunk programs
+	pub fn bytes(&self) -> Vec<u8> {
+		self.chunks.iter().flat_map(|chunk| chunk.bytes.iter().copied()).collect()
+	}
+
+	// Symbols keyed by address, for the disassembler
+	pub fn labels(&self) -> Labels {
+		self.symbols.iter().map(|(name, &addr)| (addr, name.clone())).collect()
+	}
+
+}
+
+pub fn assemble(source: &str) -> Result<Assembly, AsmError> {
+	let mut asm = Assembler { symbols: HashMap::new(), statements: Vec::new() };
+	asm.first_pass(source)?;
+	asm.second_pass()
+}
+
+
+//
+// Passes
+
+enum Item {
+	Instruction { opcode: u8, mode: AddressingMode, operand: Option<String> },
+	Bytes(Vec<String>),
+	Words(Vec<String>),
+	Constant(String, String),
+	Org,
+}
+
+struct Statement {
+	line: usize,
+	pc: u16,
+	item: Item,
+}
+
+struct Assembler {
+	symbols: HashMap<String, i64>,
+	statements: Vec<Statement>,
+}
+
+impl Assembler {
+
+	// Defines labels and fixes the size of every statement. Forward
+	// references are assumed to be absolute addresses.
+	fn first_pass(&mut self, source: &str) -> Result<(), AsmError> {
+		let mut pc: u32 = 0;
+
+		for (n, raw) in source.lines().enumerate() {
+			let line = n + 1;
+			let err = |message: String| AsmError { line, message };
+			let mut text = strip_comment(raw).trim();
+
+			// Any number of leading "label:"
+			while let Some(colon) = label_end(text) {
+				let name = &text[..colon];
+				self.define(name, pc as i64).map_err(err)?;
+				text = text[colon + 1..].trim();
+			}
+			if text.is_empty() {
+				continue;
+			}
+
+			let (word, rest) = split_word(text);
+
+			// NAME = expr
+			if let Some(expr) = rest.strip_prefix('=') {
+				if !is_identifier(word) {
+					return Err(err(format!("bad symbol name '{}'", word)));
+				}
+				let expr = expr.trim().to_string();
+				if let (value, false) = eval(&expr, &self.symbols, pc as u16, false).map_err(err)? {
+					self.define(word, value).map_err(err)?;
+				}
+				self.statements.push(Statement { line, pc: pc as u16, item: Item::Constant(word.to_string(), expr) });
+				continue;
+			}
+
+			let start = pc as u16;
+			let item = match word.to_ascii_lowercase().as_str() {
+				".org" => {
+					let (value, unresolved) = eval(rest, &self.symbols, pc as u16, false).map_err(err)?;
+					if unresolved {
+						return Err(err(".org needs an address known in advance".to_string()));
+					}
+					pc = word_value(value).map_err(err)? as u32;
+					self.statements.push(Statement { line, pc: pc as u16, item: Item::Org });
+					continue;
+				}
+				".byte" | ".db" => {
+					let items = split_list(rest);
+					for item in &items {
+						pc += string_literal(item).map_or(1, |s| s.len() as u32);
+					}
+					Item::Bytes(items)
+				}
+				".word" | ".dw" => {
+					let items = split_list(rest);
+					pc += 2 * items.len() as u32;
+					Item::Words(items)
+				}
+				_ if word.starts_with('.') => return Err(err(format!("unknown directive '{}'", word))),
+				_ => {
+					let (opcode, mode, operand) = self.instruction(word, rest, pc as u16).map_err(err)?;
+					pc += mode.bytes() as u32;
+					Item::Instruction { opcode, mode, operand }
+				}
+			};
+
+			if pc > 0x10000 {
+				return Err(err("program runs past $FFFF".to_string()));
+			}
+			self.statements.push(Statement { line, pc: start, item });
+		}
+
+		Ok(())
+	}
+
+	// Evaluates every operand now that all labels are known
+	fn second_pass(mut self) -> Result<Assembly, AsmError> {
+		let mut chunks: Vec<Chunk> = Vec::new();
+		let statements = std::mem::take(&mut self.statements);
+
+		for statement in &statements {
+			let err = |message: String| AsmError { line: statement.line, message };
+			let pc = statement.pc;
+			let value = |expr: &str| -> Result<i64, AsmError> {
+				eval(expr, &self.symbols, pc, true).map(|(value, _)| value).map_err(err)
+			};
+
+			let mut out = Vec::new();
+			match &statement.item {
+				Item::Org => {
+					chunks.push(Chunk { addr: pc, bytes: Vec::new() });
+					continue;
+				}
+				Item::Constant(name, expr) => {
+					let v = value(expr)?;
+					self.symbols.insert(name.clone(), v);
+					continue;
+				}
+				Item::Bytes(items) => {
+					for item in items {
+						match string_literal(item) {
+							Some(text) => out.extend_from_slice(text.as_bytes()),
+							None => out.push(byte_value(value(item)?).map_err(err)?),
+						}
+					}
+				}
+				Item::Words(items) => {
+					for item in items {
+						let word = word_value(value(item)?).map_err(err)?;
+						out.extend_from_slice(&word.to_le_bytes());
+					}
+				}
+				Item::Instruction { opcode, mode, operand } => {
+					out.push(*opcode);
+					let v = match operand {
+						Some(expr) => value(expr)?,
+						None => 0,
+					};
+					match mode.bytes() {
+						2 if *mode == AddressingMode::Relative => {
+							let offset = v - (pc as i64 + 2);
+							if !(-128..=127).contains(&offset) {
+								return Err(err(format!("branch target out of range ({} bytes)", offset)));
+							}
+							out.push(offset as u8);
+						}
+						2 => out.push(byte_value(v).map_err(err)?),
+						3 => out.extend_from_slice(&word_value(v).map_err(err)?.to_le_bytes()),
+						_ => {}
+					}
+				}
+			}
+
+			// Code before any .org starts at $0000
+			if chunks.is_empty() {
+				chunks.push(Chunk { addr: 0, bytes: Vec::new() });
+			}
+			chunks.last_mut().unwrap().bytes.extend(out);
+		}
+
+		chunks.retain(|chunk| !chunk.bytes.is_empty());
+		let symbols = self.symbols.iter().map(|(name, &value)| (name.clone(), value as u16)).collect();
+
+		Ok(Assembly { chunks, symbols })
+	}
+
+	fn define(&mut self, name: &str, value: i64) -> Result<(), String> {
+		if !is_identifier(name) {
+			return Err(format!("bad symbol name '{}'", name));
+		}
+		if self.symbols.insert(name.to_string(), value).is_some() {
+			return Err(format!("'{}' defined twice", name));
+		}
+		Ok(())
+	}
+
+	// Picks the opcode and addressing mode from the operand's shape
+	fn instruction(&self, mnemonic: &str, operand: &str, pc: u16) -> Result<(u8, AddressingMode, Option<String>), String> {
+		use AddressingMode::*;
+
+		let mnemonic = canonical(mnemonic);
+		if !LOOK_UP.iter().any(|op| op.mnemonic == mnemonic) {
+			return Err(format!("unknown instruction '{}'", mnemonic));
+		}
+
+		let has = |mode| find(&mnemonic, mode).is_some();
+		let operand = compact(operand);
+		let upper = operand.to_ascii_uppercase();
+
+		let (mode, expr) = if operand.is_empty() || upper == "A" {
+			(if has(Accumulator) { Accumulator } else { Implied }, None)
+		} else if let Some(expr) = operand.strip_prefix('#') {
+			(Immediate, Some(expr.to_string()))
+		} else if operand.starts_with('(') && upper.ends_with(",X)") {
+			(IndirectX, Some(operand[1..operand.len() - 3].to_string()))
+		} else if operand.starts_with('(') && upper.ends_with("),Y") {
+			(IndirectY, Some(operand[1..operand.len() - 3].to_string()))
+		} else if has(Indirect) && operand.starts_with('(') && closing_paren(&operand) == Some(operand.len() - 1) {
+			(Indirect, Some(operand[1..operand.len() - 1].to_string()))
+		} else if has(Relative) {
+			(Relative, Some(operand))
+		} else {
+			let (expr, zp, abs) = if upper.ends_with(",X") {
+				(&operand[..operand.len() - 2], ZeroPageX, AbsoluteX)
+			} else if upper.ends_with(",Y") {
+				(&operand[..operand.len() - 2], ZeroPageY, AbsoluteY)
+			} else {
+				(&operand[..], ZeroPage, Absolute)
+			};
+
+			// Forward references stay absolute unless there is no other choice
+			let mode = match eval(expr, &self.symbols, pc, false)? {
+				(value, false) if (0..=0xFF).contains(&value) && has(zp) => zp,
+				(_, false) => abs,
+				(_, true) if has(abs) => abs,
+				(_, true) => zp,
+			};
+			(mode, Some(expr.to_string()))
+		};
+
+		match find(&mnemonic, mode) {
+			Some(opcode) => Ok((opcode, mode, expr)),
+			None => Err(format!("{} has no {:?} addressing mode", mnemonic, mode)),
+		}
+	}
+
+}
+
+
+//
+// Helpers
+
+// Opcode for mnemonic + mode, preferring the official encoding
+fn find(mnemonic: &str, mode: AddressingMode) -> Option<u8> {
+	let matches = |official: bool| {
+		LOOK_UP.iter().position(|op| op.mnemonic == mnemonic && op.address_mode == mode && op.official == official)
+	};
+	matches(true).or_else(|| matches(false)).map(|i| i as u8)
+}
+
+// Other names the unofficial opcodes go by
+fn canonical(mnemonic: &str) -> String {
+	let upper = mnemonic.to_ascii_uppercase();
+	let name = match upper.as_str() {
+		"ASR" => "ALR",
+		"SBX" => "AXS",
+		"DCM" => "DCP",
+		"ISB" | "INS" => "ISC",
+		"KIL" | "HLT" => "JAM",
+		"LAR" => "LAS",
+		"ATX" => "LXA",
+		"AXA" | "AHX" => "SHA",
+		"SXA" => "SHX",
+		"SYA" => "SHY",
+		"SHS" | "XAS" => "TAS",
+		"ANE" => "XAA",
+		name => name,
+	};
+	name.to_string()
+}
+
+fn byte_value(value: i64) -> Result<u8, String> {
+	if (-128..=0xFF).contains(&value) {
+		Ok(value as u8)
+	} else {
+		Err(format!("value ${:X} does not fit in a byte", value))
+	}
+}
+
+fn word_value(value: i64) -> Result<u16, String> {
+	if (-32768..=0xFFFF).contains(&value) {
+		Ok(value as u16)
+	} else {
+		Err(format!("value ${:X} does not fit in a word", value))
+	}
+}
+
+fn is_identifier(text: &str) -> bool {
+	let mut chars = text.chars();
+	matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+		&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Position of the ':' ending a leading label
+fn label_end(text: &str) -> Option<usize> {
+	let colon = text.find(':')?;
+	is_identifier(&text[..colon]).then_some(colon)
+}
+
+// First word and the trimmed remainder
+fn split_word(text: &str) -> (&str, &str) {
+	let end = text.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(text.len());
+	(&text[..end], text[end..].trim())
+}
+
+// Walks text keeping track of quotes, calling f on every unquoted char
+fn unquoted(text: &str, mut f: impl FnMut(usize, char) -> bool) {
+	let mut quote: Option<char> = None;
+	for (i, c) in text.char_indices() {
+		match quote {
+			Some(q) if c == q => quote = None,
+			Some(_) => {}
+			None if c == '"' || c == '\'' => quote = Some(c),
+			None => if !f(i, c) { return; },
+		}
+	}
+}
+
+fn strip_comment(text: &str) -> &str {
+	let mut end = text.len();
+	unquoted(text, |i, c| {
+		if c == ';' {
+			end = i;
+			return false;
+		}
+		true
+	});
+	&text[..end]
+}
+
+// Operand with whitespace outside quotes removed
+fn compact(text: &str) -> String {
+	let mut out = String::new();
+	let mut quote: Option<char> = None;
+	for c in text.chars() {
+		match quote {
+			Some(q) if c == q => quote = None,
+			Some(_) => {}
+			None if c == '"' || c == '\'' => quote = Some(c),
+			None if c.is_whitespace() => continue,
+			None => {}
+		}
+		out.push(c);
+	}
+	out
+}
+
+fn split_list(text: &str) -> Vec<String> {
+	let mut items = Vec::new();
+	let mut start = 0;
+	unquoted(text, |i, c| {
+		if c == ',' {
+			items.push(text[start..i].trim().to_string());
+			start = i + 1;
+		}
+		true
+	});
+	items.push(text[start..].trim().to_string());
+	items.retain(|item| !item.is_empty());
+	items
+}
+
+fn string_literal(item: &str) -> Option<&str> {
+	item.strip_prefix('"')?.strip_suffix('"')
+}
+
+// Index of the ')' matching a leading '('
+fn closing_paren(text: &str) -> Option<usize> {
+	let mut depth = 0;
+	let mut found = None;
+	unquoted(text, |i, c| {
+		match c {
+			'(' => depth += 1,
+			')' => {
+				depth -= 1;
+				if depth == 0 {
+					found = Some(i);
+					return false;
+				}
+			}
+			_ => {}
+		}
+		true
+	});
+	found
+}
+
+
+//
+// Expressions
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+	Num(i64),
+	Ident(String),
+	Op(&'static str),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+	const OPS: [&str; 15] = ["<<", ">>", "+", "-", "*", "/", "%", "&", "|", "^", "~", "<", ">", "(", ")"];
+
+	let chars: Vec<char> = text.chars().collect();
+	let mut tokens = Vec::new();
+	let mut i = 0;
+
+	while i < chars.len() {
+		let c = chars[i];
+		let rest: String = chars[i..].iter().collect();
+
+		if c.is_whitespace() {
+			i += 1;
+		} else if c == '\'' && i + 2 < chars.len() && chars[i + 2] == '\'' {
+			tokens.push(Token::Num(chars[i + 1] as i64));
+			i += 3;
+		} else if c == '$' || c.is_ascii_digit() || (c == '%' && operand_expected(&tokens)) {
+			let (radix, skip) = match c {
+				'$' => (16, 1),
+				'%' => (2, 1),
+				'0' if matches!(chars.get(i + 1), Some('x' | 'X')) => (16, 2),
+				_ => (10, 0),
+			};
+			let digits: String = chars[i + skip..].iter().take_while(|c| c.is_ascii_alphanumeric()).collect();
+			let value = i64::from_str_radix(&digits, radix).map_err(|_| format!("bad number '{}'", &rest[..skip + digits.len()]))?;
+			tokens.push(Token::Num(value));
+			i += skip + digits.len();
+		} else if c.is_ascii_alphabetic() || c == '_' {
+			let name: String = chars[i..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == '_').collect();
+			i += name.len();
+			tokens.push(Token::Ident(name));
+		} else if let Some(op) = OPS.iter().find(|op| rest.starts_with(**op)) {
+			tokens.push(Token::Op(op));
+			i += op.len();
+		} else {
+			return Err(format!("unexpected '{}' in expression", c));
+		}
+	}
+
+	Ok(tokens)
+}
+
+// '%' and '*' are operators after a value, a binary number / the current
+// address where a value is expected
+fn operand_expected(tokens: &[Token]) -> bool {
+	!matches!(tokens.last(), Some(Token::Num(_) | Token::Ident(_) | Token::Op(")")))
+}
+
+// Value of expr, and whether it referred to a symbol not yet defined.
+// Unknown symbols count as 0 unless strict.
+fn eval(expr: &str, symbols: &HashMap<String, i64>, pc: u16, strict: bool) -> Result<(i64, bool), String> {
+	let tokens = tokenize(expr)?;
+	if tokens.is_empty() {
+		return Err("missing operand".to_string());
+	}
+
+	let mut parser = Parser { tokens, pos: 0, symbols, pc, strict, unresolved: false };
+	let value = parser.binary(0)?;
+	if parser.pos != parser.tokens.len() {
+		return Err(format!("unexpected {:?} in '{}'", parser.tokens[parser.pos], expr));
+	}
+
+	Ok((value, parser.unresolved))
+}
+
+struct Parser<'a> {
+	tokens: Vec<Token>,
+	pos: usize,
+	symbols: &'a HashMap<String, i64>,
+	pc: u16,
+	strict: bool,
+	unresolved: bool,
+}
+
+impl Parser<'_> {
+
+	// Binary operators, lowest precedence first
+	const LEVELS: [&'static [&'static str]; 6] = [&["|"], &["^"], &["&"], &["<<", ">>"], &["+", "-"], &["*", "/", "%"]];
+
+	fn binary(&mut self, level: usize) -> Result<i64, String> {
+		if level == Self::LEVELS.len() {
+			return self.unary();
+		}
+
+		let mut lhs = self.binary(level + 1)?;
+		while let Some(Token::Op(op)) = self.tokens.get(self.pos) {
+			let op = *op;
+			if !Self::LEVELS[level].contains(&op) {
+				break;
+			}
+			self.pos += 1;
+			let rhs = self.binary(level + 1)?;
+
+			lhs = match op {
+				"|" => lhs | rhs,
+				"^" => lhs ^ rhs,
+				"&" => lhs & rhs,
+				"<<" => lhs.checked_shl(rhs as u32).unwrap_or(0),
+				">>" => lhs.checked_shr(rhs as u32).unwrap_or(0),
+				"+" => lhs.wrapping_add(rhs),
+				"-" => lhs.wrapping_sub(rhs),
+				"*" => lhs.wrapping_mul(rhs),
+				_ if rhs == 0 => return Err("division by zero".to_string()),
+				"/" => lhs / rhs,
+				_ => lhs % rhs,
+			};
+		}
+
+		Ok(lhs)
+	}
+
+	fn unary(&mut self) -> Result<i64, String> {
+		let token = self.tokens.get(self.pos).cloned();
+		self.pos += 1;
+
+		match token {
+			Some(Token::Num(value)) => Ok(value),
+			Some(Token::Ident(name)) => match self.symbols.get(&name) {
+				Some(&value) => Ok(value),
+				None if self.strict => Err(format!("undefined symbol '{}'", name)),
+				None => {
+					self.unresolved = true;
+					Ok(0)
+				}
+			},
+			Some(Token::Op("*")) => Ok(self.pc as i64),
+			Some(Token::Op("-")) => Ok(-self.unary()?),
+			Some(Token::Op("~")) => Ok(!self.unary()?),
+			Some(Token::Op("<")) => Ok(self.unary()? & 0xFF),
+			Some(Token::Op(">")) => Ok((self.unary()? >> 8) & 0xFF),
+			Some(Token::Op("(")) => {
+				let value = self.binary(0)?;
+				if self.tokens.get(self.pos) != Some(&Token::Op(")")) {
+					return Err("missing ')'".to_string());
+				}
+				self.pos += 1;
+				Ok(value)
+			}
+			Some(token) => Err(format!("unexpected {:?}", token)),
+			None => Err("expression ends early".to_string()),
+		}
+	}
+
+}
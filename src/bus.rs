@@ -0,0 +1,51 @@
+// CPU address bus
+//
+// Every memory access the Cpu makes goes through a Bus, so each system can
+// decode its own address space (RAM mirrors, memory mapped registers,
+// cartridge space, ...).
+
+pub trait Bus {
+	fn read(&mut self, addr: u16) -> u8;
+	fn write(&mut self, addr: u16, data: u8);
+
+	// Read without side effects, for debuggers and disassemblers
+	fn peek(&self, addr: u16) -> u8;
+
+	// Scanline and dot of the PPU sharing this bus, if there is one, so
+	// traces can line up CPU and PPU timing
+	fn ppu_position(&self) -> Option<(u16, u16)> {
+		None
+	}
+}
+
+
+// Plain 64 KiB of RAM with no mapping at all, for bare 6502 experiments
+pub struct FlatRam {
+	pub mem: [u8; 64*1024],
+}
+
+impl FlatRam {
+
+	pub fn init() -> Self {
+		Self {
+			mem: [0; 64*1024],
+		}
+	}
+
+}
+
+impl Bus for FlatRam {
+
+	fn read(&mut self, addr: u16) -> u8 {
+		self.mem[addr as usize]
+	}
+
+	fn write(&mut self, addr: u16, data: u8) {
+		self.mem[addr as usize] = data;
+	}
+
+	fn peek(&self, addr: u16) -> u8 {
+		self.mem[addr as usize]
+	}
+
+}
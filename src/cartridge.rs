@@ -0,0 +1,146 @@
+// Game Pak
+//
+// The cartridge owns everything the CPU sees from $4020 upwards (PRG-ROM,
+// PRG-RAM, mapper registers) and the PPU's pattern tables at $0000-$1FFF
+// (CHR-ROM or CHR-RAM). It also decides how the console's 2 KiB of VRAM is
+// mirrored across the four nametables. Each mapper implements this trait.
+
+pub trait Cartridge {
+	fn cpu_read(&mut self, addr: u16) -> u8;
+	fn cpu_write(&mut self, addr: u16, data: u8);
+
+	// Must not clock the mapper or change its bank state, unlike a read of
+	// a register that counts or latches on access
+	fn cpu_peek(&self, addr: u16) -> u8;
+
+	fn ppu_read(&mut self, addr: u16) -> u8;
+	fn ppu_write(&mut self, addr: u16, data: u8);
+
+	fn mirroring(&self) -> Mirroring;
+}
+
+
+//
+// iNES
+//	https://www.nesdev.org/wiki/INES
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+	Horizontal,		// $2000 = $2400, $2800 = $2C00 (vertical scrolling)
+	Vertical,		// $2000 = $2800, $2400 = $2C00 (horizontal scrolling)
+	OneScreenLo,	// All four use the first 1 KiB
+	OneScreenHi,	// All four use the second 1 KiB
+	FourScreen,		// Extra RAM on the cartridge gives every nametable its own
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InesError {
+	BadMagic,
+	Truncated,
+	NoPrgRom,
+	UnsupportedMapper(u8),
+}
+
+impl std::fmt::Display for InesError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			InesError::BadMagic => write!(f, "not an iNES file"),
+			InesError::Truncated => write!(f, "file shorter than its header claims"),
+			InesError::NoPrgRom => write!(f, "image has no PRG-ROM"),
+			InesError::UnsupportedMapper(mapper) => write!(f, "mapper {} is not supported", mapper),
+		}
+	}
+}
+
+impl std::error::Error for InesError {}
+
+// Parses an iNES image and builds the cartridge for its mapper
+pub fn load_ines(data: &[u8]) -> Result<Box<dyn Cartridge>, InesError> {
+	if data.len() < 16 || &data[0..4] != b"NES\x1A" {
+		return Err(InesError::BadMagic);
+	}
+
+	let prg_size = data[4] as usize * 16*1024;
+	let chr_size = data[5] as usize * 8*1024;
+	if prg_size == 0 {
+		return Err(InesError::NoPrgRom);
+	}
+	let mapper = (data[7] & 0xF0) | (data[6] >> 4);
+	let mirroring = if data[6] & 0x08 != 0 {
+		Mirroring::FourScreen
+	} else if data[6] & 0x01 != 0 {
+		Mirroring::Vertical
+	} else {
+		Mirroring::Horizontal
+	};
+
+	// A 512 byte trainer may sit between the header and PRG-ROM
+	let prg_start = 16 + if data[6] & 0x04 != 0 { 512 } else { 0 };
+	let chr_start = prg_start + prg_size;
+	if data.len() < chr_start + chr_size {
+		return Err(InesError::Truncated);
+	}
+
+	let prg_rom = data[prg_start..chr_start].to_vec();
+	let chr_ram = chr_size == 0;
+	let chr = if chr_ram { vec![0; 8*1024] } else { data[chr_start..chr_start + chr_size].to_vec() };
+
+	match mapper {
+		0 => Ok(Box::new(Nrom { prg_rom, prg_ram: [0; 8*1024], chr, chr_ram, mirroring })),
+		_ => Err(InesError::UnsupportedMapper(mapper)),
+	}
+}
+
+
+//
+// Mapper 0: NROM
+//	https://www.nesdev.org/wiki/NROM
+//
+//	$6000-$7FFF	8 KiB PRG-RAM
+//	$8000-$FFFF	16 KiB PRG-ROM mirrored twice, or 32 KiB
+//
+//	PPU $0000-$1FFF	8 KiB CHR, fixed mirroring from the header
+
+pub struct Nrom {
+	pub prg_rom: Vec<u8>,
+	pub prg_ram: [u8; 8*1024],
+	pub chr: Vec<u8>,			// CHR-ROM, or 8 KiB CHR-RAM when the image has none
+	pub chr_ram: bool,
+	pub mirroring: Mirroring,
+}
+
+impl Cartridge for Nrom {
+
+	fn cpu_read(&mut self, addr: u16) -> u8 {
+		self.cpu_peek(addr)
+	}
+
+	fn cpu_write(&mut self, addr: u16, data: u8) {
+		if let 0x6000..=0x7FFF = addr {
+			self.prg_ram[(addr & 0x1FFF) as usize] = data;
+		}
+	}
+
+	fn cpu_peek(&self, addr: u16) -> u8 {
+		match addr {
+			0x6000..=0x7FFF => self.prg_ram[(addr & 0x1FFF) as usize],
+			0x8000..=0xFFFF => self.prg_rom[(addr as usize - 0x8000) % self.prg_rom.len()],
+			_ => 0,
+		}
+	}
+
+	fn ppu_read(&mut self, addr: u16) -> u8 {
+		self.chr[(addr & 0x1FFF) as usize]
+	}
+
+	fn ppu_write(&mut self, addr: u16, data: u8) {
+		if self.chr_ram {
+			self.chr[(addr & 0x1FFF) as usize] = data;
+		}
+	}
+
+	fn mirroring(&self) -> Mirroring {
+		self.mirroring
+	}
+
+}
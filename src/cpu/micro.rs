@@ -0,0 +1,550 @@
+//
+// Cycle-accurate core
+//
+// Each tick performs exactly the one bus access the NMOS 6502 performs on
+// that cycle, dummy reads and the double write of read-modify-write
+// instructions included. The ALU side is shared with the instruction-level
+// core through LOOK_UP; only the addressing and sequencing live here.
+//
+// Cycle by cycle reference:
+//	https://www.nesdev.org/6502_cpu.txt
+//	https://www.nesdev.org/wiki/CPU_interrupts
+
+
+use super::*;
+
+// What the operand cycle(s) do with the effective address
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Access {
+	Read,
+	Write,
+	Modify,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+	Imp,
+	Imm,
+	Zpg,
+	Zpx,
+	Zpy,
+	Abs,
+	Abx,
+	Aby,
+	Xid,
+	Idy,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Sequence {
+	Brk,
+	Jsr,
+	Rti,
+	Rts,
+	JmpAbs,
+	JmpInd,
+	Branch,
+	Push,
+	Pull,
+	Jam,
+	Memory(Mode, Access),
+}
+
+// Decode an opcode the way the 6502's PLA does, from the aaabbbcc bit groups
+fn decode(opcode: u8) -> Sequence {
+	let aaa = opcode >> 5;
+	let bbb = (opcode >> 2) & 0b111;
+	let cc = opcode & 0b11;
+
+	match opcode {
+		0x00 => return Sequence::Brk,
+		0x20 => return Sequence::Jsr,
+		0x40 => return Sequence::Rti,
+		0x60 => return Sequence::Rts,
+		0x4C => return Sequence::JmpAbs,
+		0x6C => return Sequence::JmpInd,
+		0x08 | 0x48 => return Sequence::Push,
+		0x28 | 0x68 => return Sequence::Pull,
+		_ if opcode & 0x1F == 0x10 => return Sequence::Branch,
+		_ if opcode & 0x1F == 0x12 || matches!(opcode, 0x02 | 0x22 | 0x42 | 0x62) => return Sequence::Jam,
+		_ => {}
+	}
+
+	// STX/LDX and their unofficial neighbours index with Y instead of X
+	let uses_y = cc & 0b10 != 0 && (aaa == 4 || aaa == 5);
+
+	let mode = match (bbb, cc) {
+		(0, 0b01) | (0, 0b11) => Mode::Xid,
+		(0, _) => Mode::Imm,
+		(1, _) => Mode::Zpg,
+		(2, 0b01) | (2, 0b11) => Mode::Imm,
+		(2, _) => Mode::Imp,
+		(3, _) => Mode::Abs,
+		(4, _) => if cc & 0b01 != 0 { Mode::Idy } else { Mode::Imp },
+		(5, _) => if uses_y { Mode::Zpy } else { Mode::Zpx },
+		(6, 0b01) | (6, 0b11) => Mode::Aby,
+		(6, _) => Mode::Imp,
+		_ => if uses_y { Mode::Aby } else { Mode::Abx },
+	};
+
+	let access = match (cc, aaa) {
+		_ if mode == Mode::Imm || mode == Mode::Imp => Access::Read,
+		(_, 4) => Access::Write,
+		(0b00, _) | (0b01, _) | (_, 5) => Access::Read,
+		_ => Access::Modify,
+	};
+
+	Sequence::Memory(mode, access)
+}
+
+
+impl Cpu {
+
+	pub(super) fn tick(&mut self, bus: &mut dyn Bus) {
+		if self.step == 0 {
+			self.begin(bus);
+			return;
+		}
+
+		let sequence = decode(self.opcode);
+
+		// Interrupts are polled at the end of the second to last cycle, so
+		// whatever is pending before the last cycle is what gets serviced.
+		// Taken branches that stay on the page skip the poll on cycle 2.
+		if !(sequence == Sequence::Branch && self.step == 2) {
+			self.interrupt_latch = self.pending_interrupt();
+		}
+
+		let done = match sequence {
+			Sequence::Brk => self.brk_step(bus),
+			Sequence::Jsr => self.jsr_step(bus),
+			Sequence::Rti => self.rti_step(bus),
+			Sequence::Rts => self.rts_step(bus),
+			Sequence::JmpAbs => self.jmp_abs_step(bus),
+			Sequence::JmpInd => self.jmp_ind_step(bus),
+			Sequence::Branch => self.branch_step(bus),
+			Sequence::Push => self.push_step(bus),
+			Sequence::Pull => self.pull_step(bus),
+			Sequence::Jam => {
+				self.execute(bus);
+				true
+			}
+			Sequence::Memory(mode, access) => self.memory_step(bus, mode, access),
+		};
+
+		if done {
+			self.step = 0;
+		} else {
+			self.step += 1;
+		}
+	}
+
+	// Cycle 0: fetch the next opcode, or force a BRK into the instruction
+	// register if the last poll found an interrupt
+	fn begin(&mut self, bus: &mut dyn Bus) {
+		self.delayed_i = None;
+
+		match self.interrupt_latch.take() {
+			Some(interrupt) => {
+				if interrupt == Interrupt::Nmi {
+					self.nmi_pending = false;
+				}
+				self.servicing = Some(interrupt);
+
+				bus.read(self.pc);
+				self.opcode = 0x00;
+			}
+			None => {
+				self.emit_trace(bus);
+				self.opcode = bus.read(self.pc);
+				self.pc = self.pc.wrapping_add(1);
+			}
+		}
+
+		self.step = 1;
+	}
+
+	fn execute(&mut self, bus: &mut dyn Bus) {
+		(LOOK_UP[self.opcode as usize].instruction)(self, bus);
+	}
+
+	fn fetch_pc(&mut self, bus: &mut dyn Bus) -> u8 {
+		let data = bus.read(self.pc);
+		self.pc = self.pc.wrapping_add(1);
+		data
+	}
+
+
+	//
+	// Memory operands
+
+	fn memory_step(&mut self, bus: &mut dyn Bus, mode: Mode, access: Access) -> bool {
+		match (mode, self.step) {
+			(Mode::Imp, _) => {
+				bus.read(self.pc);
+				self.fetched = self.ac;
+				self.execute(bus);
+				true
+			}
+
+			(Mode::Imm, _) => {
+				self.addr_abs = self.pc;
+				self.fetched = self.fetch_pc(bus);
+				self.execute(bus);
+				true
+			}
+
+			(Mode::Zpg, 1) => {
+				self.addr_abs = self.fetch_pc(bus) as u16;
+				false
+			}
+			(Mode::Zpg, n) => self.operand_step(bus, access, n - 2),
+
+			(Mode::Zpx | Mode::Zpy, 1) => {
+				self.addr_abs = self.fetch_pc(bus) as u16;
+				false
+			}
+			(Mode::Zpx | Mode::Zpy, 2) => {
+				bus.read(self.addr_abs);
+				let index = if mode == Mode::Zpx { self.x } else { self.y };
+				self.addr_abs = (self.addr_abs as u8).wrapping_add(index) as u16;
+				false
+			}
+			(Mode::Zpx | Mode::Zpy, n) => self.operand_step(bus, access, n - 3),
+
+			(Mode::Abs, 1) => {
+				self.addr_abs = self.fetch_pc(bus) as u16;
+				false
+			}
+			(Mode::Abs, 2) => {
+				self.addr_abs |= (self.fetch_pc(bus) as u16) << 8;
+				false
+			}
+			(Mode::Abs, n) => self.operand_step(bus, access, n - 3),
+
+			(Mode::Abx | Mode::Aby, 1) => {
+				self.addr_abs = self.fetch_pc(bus) as u16;
+				false
+			}
+			(Mode::Abx | Mode::Aby, 2) => {
+				let hi = self.fetch_pc(bus);
+				let index = if mode == Mode::Abx { self.x } else { self.y };
+				self.index_address(hi, index);
+				false
+			}
+			(Mode::Abx | Mode::Aby, 3) => self.fixup_step(bus, access),
+			(Mode::Abx | Mode::Aby, n) => self.operand_step(bus, access, n - 4),
+
+			(Mode::Xid, 1) => {
+				self.ptr = self.fetch_pc(bus);
+				false
+			}
+			(Mode::Xid, 2) => {
+				bus.read(self.ptr as u16);
+				self.ptr = self.ptr.wrapping_add(self.x);
+				false
+			}
+			(Mode::Xid, 3) => {
+				self.addr_abs = bus.read(self.ptr as u16) as u16;
+				false
+			}
+			(Mode::Xid, 4) => {
+				self.addr_abs |= (bus.read(self.ptr.wrapping_add(1) as u16) as u16) << 8;
+				false
+			}
+			(Mode::Xid, n) => self.operand_step(bus, access, n - 5),
+
+			(Mode::Idy, 1) => {
+				self.ptr = self.fetch_pc(bus);
+				false
+			}
+			(Mode::Idy, 2) => {
+				self.addr_abs = bus.read(self.ptr as u16) as u16;
+				false
+			}
+			(Mode::Idy, 3) => {
+				let hi = bus.read(self.ptr.wrapping_add(1) as u16);
+				self.index_address(hi, self.y);
+				false
+			}
+			(Mode::Idy, 4) => self.fixup_step(bus, access),
+			(Mode::Idy, n) => self.operand_step(bus, access, n - 5),
+		}
+	}
+
+	// Add the index to the low byte only; the carry into the high byte
+	// costs the fixup cycle
+	fn index_address(&mut self, hi: u8, index: u8) {
+		let (lo, carry) = (self.addr_abs as u8).overflowing_add(index);
+		self.addr_abs = ((hi as u16) << 8) | lo as u16;
+		self.page_crossed = carry;
+	}
+
+	// Read from the possibly wrong address. Reads that didn't cross a page
+	// are done here; everything else treats it as a dummy read.
+	fn fixup_step(&mut self, bus: &mut dyn Bus, access: Access) -> bool {
+		if access == Access::Read && !self.page_crossed {
+			return self.operand_step(bus, access, 0);
+		}
+
+		bus.read(self.addr_abs);
+		if self.page_crossed {
+			self.addr_abs = self.addr_abs.wrapping_add(0x0100);
+		}
+
+		false
+	}
+
+	// Cycles spent on the effective address itself
+	fn operand_step(&mut self, bus: &mut dyn Bus, access: Access, n: u8) -> bool {
+		match (access, n) {
+			(Access::Read, _) => {
+				self.fetched = bus.read(self.addr_abs);
+				self.execute(bus);
+				true
+			}
+			(Access::Write, _) => {
+				self.execute(bus);
+				true
+			}
+			(Access::Modify, 0) => {
+				self.fetched = bus.read(self.addr_abs);
+				false
+			}
+			(Access::Modify, 1) => {
+				bus.write(self.addr_abs, self.fetched);
+				false
+			}
+			(Access::Modify, _) => {
+				self.execute(bus);
+				true
+			}
+		}
+	}
+
+
+	//
+	// Control flow and stack
+
+	// Shared by BRK, IRQ and NMI. Hardware interrupts don't step PC past
+	// the padding byte and push B clear. An NMI that shows up before the
+	// status push takes over the vector of a BRK/IRQ in progress.
+	fn brk_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				bus.read(self.pc);
+				if self.servicing.is_none() {
+					self.pc = self.pc.wrapping_add(1);
+				}
+				false
+			}
+			2 => {
+				self.push(bus, (self.pc >> 8) as u8);
+				false
+			}
+			3 => {
+				self.push(bus, self.pc as u8);
+				false
+			}
+			4 => {
+				self.push(bus, self.status_to_push(self.servicing.is_none()));
+				self.sr.set(Status::I, true);
+
+				self.addr_abs = if self.servicing == Some(Interrupt::Nmi) || self.nmi_pending {
+					self.nmi_pending = false;
+					Interrupt::Nmi.vector()
+				} else {
+					Interrupt::Irq.vector()
+				};
+				false
+			}
+			5 => {
+				self.pc = bus.read(self.addr_abs) as u16;
+				false
+			}
+			_ => {
+				self.pc |= (bus.read(self.addr_abs + 1) as u16) << 8;
+				self.servicing = None;
+				self.interrupt_latch = None;
+				true
+			}
+		}
+	}
+
+	fn jsr_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				self.addr_abs = self.fetch_pc(bus) as u16;
+				false
+			}
+			2 => {
+				bus.read(0x0100 + self.sp as u16);
+				false
+			}
+			3 => {
+				self.push(bus, (self.pc >> 8) as u8);
+				false
+			}
+			4 => {
+				self.push(bus, self.pc as u8);
+				false
+			}
+			_ => {
+				self.addr_abs |= (bus.read(self.pc) as u16) << 8;
+				self.pc = self.addr_abs;
+				true
+			}
+		}
+	}
+
+	fn rti_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				bus.read(self.pc);
+				false
+			}
+			2 => {
+				bus.read(0x0100 + self.sp as u16);
+				false
+			}
+			3 => {
+				self.pull_status(bus);
+				false
+			}
+			4 => {
+				self.pc = self.pull(bus) as u16;
+				false
+			}
+			_ => {
+				self.pc |= (self.pull(bus) as u16) << 8;
+				true
+			}
+		}
+	}
+
+	fn rts_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				bus.read(self.pc);
+				false
+			}
+			2 => {
+				bus.read(0x0100 + self.sp as u16);
+				false
+			}
+			3 => {
+				self.pc = self.pull(bus) as u16;
+				false
+			}
+			4 => {
+				self.pc |= (self.pull(bus) as u16) << 8;
+				false
+			}
+			_ => {
+				self.fetch_pc(bus);
+				true
+			}
+		}
+	}
+
+	fn jmp_abs_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				self.addr_abs = self.fetch_pc(bus) as u16;
+				false
+			}
+			_ => {
+				self.addr_abs |= (bus.read(self.pc) as u16) << 8;
+				self.pc = self.addr_abs;
+				true
+			}
+		}
+	}
+
+	// The pointer's high byte is never incremented: JMP ($xxFF) reads the
+	// target's high byte from $xx00
+	fn jmp_ind_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				self.addr_abs = self.fetch_pc(bus) as u16;
+				false
+			}
+			2 => {
+				self.addr_abs |= (self.fetch_pc(bus) as u16) << 8;
+				false
+			}
+			3 => {
+				self.fetched = bus.read(self.addr_abs);
+				false
+			}
+			_ => {
+				let hi_addr = (self.addr_abs & 0xFF00) | (self.addr_abs.wrapping_add(1) & 0x00FF);
+				self.pc = ((bus.read(hi_addr) as u16) << 8) | self.fetched as u16;
+				true
+			}
+		}
+	}
+
+	// Bits 7-6 of a branch opcode pick the flag, bit 5 the value to branch on
+	fn branch_taken(&self) -> bool {
+		let flag = match self.opcode >> 6 {
+			0 => Status::N,
+			1 => Status::V,
+			2 => Status::C,
+			_ => Status::Z,
+		};
+
+		self.sr.contains(flag) == ((self.opcode >> 5) & 1 != 0)
+	}
+
+	fn branch_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				self.addr_rel = self.fetch_pc(bus) as i8 as u16;
+				!self.branch_taken()
+			}
+			2 => {
+				bus.read(self.pc);
+				self.addr_abs = self.pc.wrapping_add(self.addr_rel);
+				self.pc = (self.pc & 0xFF00) | (self.addr_abs & 0x00FF);
+				self.pc == self.addr_abs
+			}
+			_ => {
+				bus.read(self.pc);
+				self.pc = self.addr_abs;
+				true
+			}
+		}
+	}
+
+	fn push_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				bus.read(self.pc);
+				false
+			}
+			_ => {
+				self.execute(bus);
+				true
+			}
+		}
+	}
+
+	fn pull_step(&mut self, bus: &mut dyn Bus) -> bool {
+		match self.step {
+			1 => {
+				bus.read(self.pc);
+				false
+			}
+			2 => {
+				bus.read(0x0100 + self.sp as u16);
+				false
+			}
+			_ => {
+				self.execute(bus);
+				true
+			}
+		}
+	}
+
+}
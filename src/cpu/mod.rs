@@ -11,21 +11,25 @@
 //	https://www.nesdev.org/obelisk-6502-guide/index.html
 
 
+mod micro;
 mod opcodes;
-use opcodes::*;
+mod status;
+mod trace;
+pub use opcodes::{AddressingMode, Opcodes, LOOK_UP, LOOK_UP_65C02};
+pub use status::Status;
+use crate::bus::Bus;
 
 // 6502 Processor
 pub struct Cpu {
 	pub opcode: u8,
 
-	pub mem: [u8; 64*1024],
 	pub pc: u16,
 	pub sp: u8,				// Stack locatated between $0100 and $01FF
 							// decrement on push, increment on pop
 	pub ac: u8,
 	pub x: u8,
 	pub y: u8,
-	pub sr: u8,				// Negative, Overflow, 1, Break, Decimal, Interrupt, Zero, Carry
+	pub sr: Status,			// Negative, Overflow, 1, Break, Decimal, Interrupt, Zero, Carry
 
 	pub addr_abs: u16,
 	pub addr_rel: u16,
@@ -33,100 +37,481 @@ pub struct Cpu {
 
 	pub cycles: u8,
 	pub global_clock: u128,
+
+	pub nmi_line: bool,		// Current level of the NMI input
+	pub nmi_pending: bool,	// Latched on the inactive -> active edge
+	pub nmi_edge_clock: u128,
+	pub irq_lines: u8,		// One bit per IrqSource currently holding IRQ
+
+	pub delayed_i: Option<bool>,	// I flag seen by the next poll after CLI/SEI/PLP
+	pub vector_pending: Option<u16>,	// BRK/IRQ/NMI sequence waiting on its vector fetch
+	pub sequence_start: u128,
+
+	variant: Variant,		// Fixed by with_variant, along with its opcode table
+	look_up: &'static [Opcodes; 256],
+	pub mode: StepMode,
+	pub step: u8,			// Cycle within the current instruction (cycle-accurate core)
+	pub ptr: u8,			// Zero page pointer for (zp,X) / (zp),Y
+	pub page_crossed: bool,
+	pub interrupt_latch: Option<Interrupt>,
+	pub servicing: Option<Interrupt>,
+
+	pub state: CpuState,
+
+	pub trace: Option<TraceSink>,	// Receives a nestest style line per instruction
+}
+
+// Receives one trace line per instruction
+pub type TraceSink = Box<dyn FnMut(&str)>;
+
+// What step_instruction ran
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+	pub pc: u16,
+	pub opcode: u8,
+	pub cycles: u32,
+	pub interrupt: Option<Interrupt>,	// Set when this was an NMI/IRQ sequence rather than an instruction
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuState {
+	Running,
+	Halted { pc: u16, opcode: u8 },		// Locked up by a JAM/KIL (or 65C02 STP) opcode until reset
+	Waiting,							// 65C02 WAI: idle until IRQ or NMI
+}
+
+// Which chip the core behaves as
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Variant {
+	#[default]
+	Ricoh2A03,		// NES CPU: decimal mode disconnected, D is just a flag
+	Nmos6502,		// MOS 6502 with BCD ADC/SBC
+	Wdc65C02,		// CMOS 65C02 with the WDC and Rockwell additions
+}
+
+impl Variant {
+
+	pub fn opcodes(self) -> &'static [Opcodes; 256] {
+		match self {
+			Variant::Wdc65C02 => &LOOK_UP_65C02,
+			_ => &LOOK_UP,
+		}
+	}
+
+	pub fn decimal_mode(self) -> bool {
+		self != Variant::Ricoh2A03
+	}
+
+}
+
+// How Cpu::cycle drives execution. Only switch at instruction boundaries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepMode {
+	Instruction,	// Whole instruction on its first cycle, then idle cycles
+	Cycle,			// One bus access per cycle, as the real chip does
+}
+
+// Interrupts that can be waiting at an instruction boundary
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+	Nmi,
+	Irq,
+}
+
+impl Interrupt {
+
+	pub fn vector(self) -> u16 {
+		match self {
+			Interrupt::Nmi => 0xFFFA,
+			Interrupt::Irq => 0xFFFE,
+		}
+	}
+
+}
+
+// Devices sharing the (wired-OR) IRQ line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IrqSource {
+	FrameCounter	= 0b0001,
+	Dmc				= 0b0010,
+	Mapper			= 0b0100,
+	External		= 0b1000,
 }
 
 impl Cpu {
 
+	// Bare register file with PC at $0200, handy for test programs.
+	// Use power_on / reset to start from the reset vector like real hardware.
 	pub fn init() -> Self {
+		Self::with_variant(Variant::default())
+	}
+
+	// init() for a chip other than the NES 2A03
+	pub fn with_variant(variant: Variant) -> Self {
 		Self {
 			opcode: 0x00,
 
-			mem: [0; 64*1024],
 			pc: 0x0200,
 			sp: 0xFF,
 			ac: 0x00,
 			x: 0x00,
 			y: 0x00,
-			sr: 0b00100000,
+			sr: Status::U,
 
 			addr_abs: 0,
 			addr_rel: 0,
 			fetched: 0,
 
 			cycles: 0,
-			global_clock: 0
+			global_clock: 0,
+
+			nmi_line: false,
+			nmi_pending: false,
+			nmi_edge_clock: 0,
+			irq_lines: 0,
+
+			delayed_i: None,
+			vector_pending: None,
+			sequence_start: 0,
+
+			variant,
+			look_up: variant.opcodes(),
+			mode: StepMode::Instruction,
+			step: 0,
+			ptr: 0,
+			page_crossed: false,
+			interrupt_latch: None,
+			servicing: None,
+
+			state: CpuState::Running,
+
+			trace: None,
 		}
 	}
 
-	pub fn cycle(&mut self) {
+	pub fn variant(&self) -> Variant {
+		self.variant
+	}
+
+	// Cold boot: registers take their power-up values, then the reset sequence runs
+	pub fn power_on(&mut self, bus: &mut dyn Bus) {
+		self.ac = 0x00;
+		self.x = 0x00;
+		self.y = 0x00;
+		self.sp = 0x00;			// The reset sequence leaves this at $FD
+		self.sr = Status::U;
+		self.global_clock = 0;
+
+		self.reset(bus);
+	}
+
+	// Warm reset: A, X, Y and flags other than I are preserved. The CPU runs
+	// three suppressed pushes (SP - 3) and then jumps through $FFFC/$FFFD.
+	pub fn reset(&mut self, bus: &mut dyn Bus) {
+		self.sp = self.sp.wrapping_sub(3);
+		self.sr.set(Status::I, true);
+
+		let lo = bus.read(0xFFFC) as u16;
+		let hi = bus.read(0xFFFD) as u16;
+		self.pc = (hi << 8) | lo;
+
+		self.addr_abs = 0;
+		self.addr_rel = 0;
+		self.fetched = 0;
+		self.nmi_pending = false;
+		self.delayed_i = None;
+		self.vector_pending = None;
+		self.step = 0;
+		self.interrupt_latch = None;
+		self.servicing = None;
+		self.state = CpuState::Running;
+
+		self.cycles = 7;
+	}
+
+	pub fn cycle(&mut self, bus: &mut dyn Bus) {
 		self.global_clock += 1;
+		if self.is_halted() {
+			return;
+		}
+
 		if self.cycles != 0 {
 			self.cycles -= 1;
+			if self.cycles == 0 {
+				self.end_of_instruction(bus);
+			}
 			return;
 		}
 
-		self.opcode = self.mem[self.pc as usize];
-		self.pc += 1;
+		// WAI wakes on any IRQ or NMI; with I set it just carries on
+		if self.state == CpuState::Waiting {
+			if !self.nmi_pending && !self.irq_active() {
+				return;
+			}
+			self.state = CpuState::Running;
+			self.interrupt_latch = self.pending_interrupt();
+		}
 
-		self.cycles = LOOK_UP[self.opcode as usize].cycles;
-		let extra_cycles1 = (LOOK_UP[self.opcode as usize].address_mode)(self);
-		let extra_cycles2 = (LOOK_UP[self.opcode as usize].instruction)(self);
+		if self.cycle_core() {
+			self.tick(bus);
+			return;
+		}
+
+		if let Some(interrupt) = self.interrupt_latch.take() {
+			if interrupt == Interrupt::Nmi {
+				self.nmi_pending = false;
+			}
+			self.servicing = Some(interrupt);
+			self.opcode = 0x00;
+
+			self.interrupt(bus, interrupt.vector(), false);
+			self.cycles = 7 - 1;
+			return;
+		}
+
+		self.emit_trace(bus);
+		self.opcode = bus.read(self.pc);
+		self.pc = self.pc.wrapping_add(1);
+
+		// Base time from the table, plus a cycle when indexing crosses a
+		// page (reads only, per the table) and whatever the instruction
+		// itself adds: taken branches, 65C02 decimal arithmetic
+		let op = &self.look_up[self.opcode as usize];
+		let page_crossed = op.address_mode.address(self, bus);
+		let extra_cycles = (op.instruction)(self, bus);
 
-		self.cycles += extra_cycles1 & extra_cycles2;
+		self.cycles = op.cycles + extra_cycles;
+		if op.page_penalty {
+			self.cycles += page_crossed;
+		}
+
+		// This tick was the instruction's first cycle
+		self.cycles = self.cycles.saturating_sub(1);
+		if self.cycles == 0 {
+			self.end_of_instruction(bus);
+		}
 	}
 
-	pub fn fetch(&mut self) -> u8 {
-		if LOOK_UP[self.opcode as usize].address_mode != imp {
-			self.fetched = self.mem[self.addr_abs as usize];
+	// Last cycle of an instruction in the instruction-level core. Interrupt
+	// sequences fetch their vector here and skip the poll, so one handler
+	// instruction always runs before the next interrupt is taken.
+	fn end_of_instruction(&mut self, bus: &mut dyn Bus) {
+		if let Some(vector) = self.vector_pending.take() {
+			let vector = self.hijack(vector);
+			self.pc = bus.read(vector) as u16 | ((bus.read(vector + 1) as u16) << 8);
+			self.servicing = None;
+			return;
 		}
 
-		self.fetched
+		self.interrupt_latch = self.pending_interrupt();
+		self.delayed_i = None;
+	}
+
+	// True between instructions, i.e. the next cycle starts a new instruction
+	// or interrupt sequence
+	pub fn at_boundary(&self) -> bool {
+		self.cycles == 0 && (!self.cycle_core() || self.step == 0)
+	}
+
+	// Whether tick runs the instructions. The cycle-accurate core follows
+	// the NMOS bus pattern, so a 65C02 always runs whole instructions.
+	fn cycle_core(&self) -> bool {
+		self.mode == StepMode::Cycle && self.variant != Variant::Wdc65C02
 	}
 
 
 	//
-	// Flags
-
-	pub fn get_flag(&self, flag: char) -> u8 {
-		match flag.to_ascii_uppercase() {
-			'C' => (self.sr & 0b00000001) >> 0,
-			'Z' => (self.sr & 0b00000010) >> 1,
-			'I' => (self.sr & 0b00000100) >> 2,
-			'D' => (self.sr & 0b00001000) >> 3,
-			'B' => (self.sr & 0b00010000) >> 4,
-			'V' => (self.sr & 0b01000000) >> 6,
-			'N' => (self.sr & 0b10000000) >> 7,
-
-			_ => 0
-		}
-	}
-
-	pub fn set_flag(&mut self, flag: char, cond: bool) {
-		if cond {
-			self.sr |= match flag.to_ascii_uppercase() {
-				'C' => 0b00000001,
-				'Z' => 0b00000010,
-				'I' => 0b00000100,
-				'D' => 0b00001000,
-				'B' => 0b00010000,
-				'V' => 0b01000000,
-				'N' => 0b10000000,
-
-				_ => self.sr
+	// Stepping
+
+	// Finish whatever is in flight, then run exactly one instruction (or
+	// interrupt sequence) to completion. Runs nothing once is_stopped().
+	pub fn step_instruction(&mut self, bus: &mut dyn Bus) -> Step {
+		while !self.at_boundary() && !self.is_halted() {
+			self.cycle(bus);
+		}
+
+		let pc = self.pc;
+		let start = self.global_clock;
+		let mut interrupt = None;
+
+		if !self.is_stopped() {
+			self.cycle(bus);
+			interrupt = self.servicing;
+
+			while !self.at_boundary() && !self.is_halted() {
+				self.cycle(bus);
 			}
+		}
+
+		Step {
+			pc,
+			opcode: self.opcode,
+			cycles: (self.global_clock - start) as u32,
+			interrupt,
+		}
+	}
+
+	pub fn run_cycles(&mut self, bus: &mut dyn Bus, n: u64) -> CpuState {
+		for _ in 0..n {
+			self.cycle(bus);
+		}
+
+		self.state
+	}
+
+	// Run whole instructions until PC reaches addr. Returns straight away if
+	// it is already there.
+	pub fn run_until_pc(&mut self, bus: &mut dyn Bus, addr: u16) -> CpuState {
+		self.run_until(bus, |cpu, _| cpu.pc == addr)
+	}
+
+	// Run whole instructions until the predicate holds at an instruction
+	// boundary, or the CPU stops: jammed, or waiting with nothing to wake it
+	pub fn run_until(&mut self, bus: &mut dyn Bus, mut done: impl FnMut(&Cpu, &dyn Bus) -> bool) -> CpuState {
+		while !self.is_stopped() && !done(self, bus) {
+			self.step_instruction(bus);
+		}
+
+		self.state
+	}
+
+	// A jammed CPU ignores everything, interrupts included, until reset
+	pub fn is_halted(&self) -> bool {
+		matches!(self.state, CpuState::Halted { .. })
+	}
+
+	// True when cycles go by without anything running: jammed, or finished
+	// a WAI with neither NMI nor IRQ to wake it
+	pub fn is_stopped(&self) -> bool {
+		match self.state {
+			CpuState::Running => false,
+			CpuState::Halted { .. } => true,
+			CpuState::Waiting => self.at_boundary() && !self.nmi_pending && !self.irq_active(),
+		}
+	}
+
+	fn halt(&mut self, pc: u16) {
+		self.state = CpuState::Halted { pc, opcode: self.opcode };
+	}
+
+	// The cycle-accurate core reads operands on their own cycle, so there
+	// fetched is already loaded
+	pub fn fetch(&mut self, bus: &mut dyn Bus) -> u8 {
+		let address_mode = self.address_mode();
+		if !self.cycle_core() && address_mode != AddressingMode::Implied && address_mode != AddressingMode::Accumulator {
+			self.fetched = bus.read(self.addr_abs);
+		}
+
+		self.fetched
+	}
+
+	// ADC/SBC work in BCD
+	fn decimal(&self) -> bool {
+		self.sr.contains(Status::D) && self.variant.decimal_mode()
+	}
+
+	pub fn address_mode(&self) -> AddressingMode {
+		self.look_up[self.opcode as usize].address_mode
+	}
+
+
+	//
+	// Stack
+
+	pub fn push(&mut self, bus: &mut dyn Bus, data: u8) {
+		bus.write(0x0100 + self.sp as u16, data);
+		self.sp = self.sp.wrapping_sub(1);
+	}
+
+	pub fn pull(&mut self, bus: &mut dyn Bus) -> u8 {
+		self.sp = self.sp.wrapping_add(1);
+		bus.read(0x0100 + self.sp as u16)
+	}
+
+
+	//
+	// Interrupts
+
+	// NMI is edge triggered: going active latches a pending NMI, which is
+	// serviced once even if the line is held
+	pub fn set_nmi(&mut self, active: bool) {
+		if active && !self.nmi_line {
+			self.nmi_pending = true;
+			self.nmi_edge_clock = self.global_clock;
+		}
+		self.nmi_line = active;
+	}
+
+	// IRQ is level triggered and shared: it stays asserted while any source holds it
+	pub fn set_irq(&mut self, source: IrqSource, active: bool) {
+		if active {
+			self.irq_lines |= source as u8;
 		} else {
-			self.sr &= match flag.to_ascii_uppercase() {
-				'C' => 0b11111110,
-				'Z' => 0b11111101,
-				'I' => 0b11111011,
-				'D' => 0b11110111,
-				'B' => 0b11101111,
-				'V' => 0b10111111,
-				'N' => 0b01111111,
-	
-				_ => self.sr
-			}
+			self.irq_lines &= !(source as u8);
 		}
 	}
 
+	pub fn irq_active(&self) -> bool {
+		self.irq_lines != 0
+	}
+
+	// What the next poll would service. NMI wins over IRQ, and IRQ is masked
+	// by the I flag - as it was before the last CLI/SEI/PLP, since those
+	// change I only after the poll on their final cycle.
+	pub fn pending_interrupt(&self) -> Option<Interrupt> {
+		if self.nmi_pending {
+			return Some(Interrupt::Nmi);
+		}
+
+		if self.irq_active() && !self.delayed_i.unwrap_or(self.sr.contains(Status::I)) {
+			return Some(Interrupt::Irq);
+		}
+
+		None
+	}
+
+	// Push PC and status, then leave the vector fetch for the end of the
+	// sequence. The pushed status has bit 5 set, and B set only for BRK.
+	fn interrupt(&mut self, bus: &mut dyn Bus, vector: u16, brk: bool) {
+		self.push(bus, ((self.pc & 0xFF00) >> 8) as u8);
+		self.push(bus, (self.pc & 0x00FF) as u8);
+		self.push(bus, self.status_to_push(brk));
+		self.sr.set(Status::I, true);
+		if self.variant == Variant::Wdc65C02 {
+			self.sr.remove(Status::D);
+		}
+
+		self.vector_pending = Some(vector);
+		self.sequence_start = self.global_clock;
+	}
+
+	// An NMI that arrives during the first four cycles of a BRK/IRQ sequence
+	// takes over its vector fetch; the pushed B flag is left as it was.
+	fn hijack(&mut self, vector: u16) -> u16 {
+		if vector != 0xFFFA && self.nmi_pending && self.nmi_edge_clock < self.sequence_start + 4 {
+			self.nmi_pending = false;
+			return Interrupt::Nmi.vector();
+		}
+
+		vector
+	}
+
+	// CLI/SEI/PLP: the new I flag is only seen by the poll after the next instruction
+	fn delay_i(&mut self, old: bool) {
+		self.delayed_i = Some(old);
+	}
+
+	fn status_to_push(&self, brk: bool) -> u8 {
+		if brk {
+			(self.sr | Status::U | Status::B).bits()
+		} else {
+			((self.sr | Status::U) & !Status::B).bits()
+		}
+	}
+
+	// B and bit 5 don't exist in the register, so they are dropped on pull
+	fn pull_status(&mut self, bus: &mut dyn Bus) {
+		self.sr = Status::from_bits(self.pull(bus)) & !Status::B | Status::U;
+	}
+
 }
\ No newline at end of file
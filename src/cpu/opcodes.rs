@@ -1,560 +1,831 @@
 use super::*;
+use crate::bus::Bus;
+
+mod cmos;
+pub use cmos::LOOK_UP_65C02;
 
 pub struct Opcodes {
-	pub instruction: fn(&mut Cpu) -> u8,
-	pub address_mode: fn(&mut Cpu) -> u8,
+	pub mnemonic: &'static str,
+	pub instruction: fn(&mut Cpu, &mut dyn Bus) -> u8,
+	pub address_mode: AddressingMode,
 	pub cycles: u8,
+	pub bytes: u8,
+	pub official: bool,
+	pub page_penalty: bool,	// +1 cycle when the indexed address crosses a page
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressingMode {
+	Implied,
+	Accumulator,
+	Immediate,
+	ZeroPage,
+	ZeroPageX,
+	ZeroPageY,
+	Relative,
+	Absolute,
+	AbsoluteX,
+	AbsoluteY,
+	Indirect,
+	IndirectX,
+	IndirectY,
+	ZeroPageIndirect,	// 65C02 (zp)
+	AbsoluteIndirectX,	// 65C02 (abs,X)
+	ZeroPageRelative,	// 65C02 BBR/BBS zp,rel
+}
+
+impl AddressingMode {
+	// Instruction length including the opcode byte
+	pub const fn bytes(self) -> u8 {
+		use AddressingMode::*;
+		match self {
+			Implied | Accumulator => 1,
+			Immediate | ZeroPage | ZeroPageX | ZeroPageY | Relative | IndirectX | IndirectY | ZeroPageIndirect => 2,
+			Absolute | AbsoluteX | AbsoluteY | Indirect | AbsoluteIndirectX | ZeroPageRelative => 3,
+		}
+	}
+
+	// Reads the operand bytes and resolves the effective address
+	pub(super) fn address(self, cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+		use AddressingMode::*;
+		match self {
+			Implied | Accumulator => imp(cpu, bus),
+			Immediate => imm(cpu, bus),
+			ZeroPage => zpg(cpu, bus),
+			ZeroPageX => zpx(cpu, bus),
+			ZeroPageY => zpy(cpu, bus),
+			Relative => rel(cpu, bus),
+			Absolute => abs(cpu, bus),
+			AbsoluteX => abx(cpu, bus),
+			AbsoluteY => aby(cpu, bus),
+			Indirect if cpu.variant == Variant::Wdc65C02 => cmos::ind(cpu, bus),
+			Indirect => ind(cpu, bus),
+			IndirectX => xid(cpu, bus),
+			IndirectY => idy(cpu, bus),
+			ZeroPageIndirect => cmos::izp(cpu, bus),
+			AbsoluteIndirectX => cmos::iax(cpu, bus),
+			ZeroPageRelative => cmos::zpr(cpu, bus),
+		}
+	}
 }
 
 
 //
 // Opcodes
 
-fn adc(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
-
-	let tmp: u16 = cpu.ac as u16 + cpu.fetched as u16 + cpu.get_flag('C') as u16;
-	cpu.set_flag('C', tmp > 255);
-	cpu.set_flag('Z', (tmp & 0x00FF) == 0);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
-	cpu.set_flag('V', ((tmp & cpu.ac as u16) & (tmp ^ cpu.fetched as u16) & 0x0080) != 0);
-
-	cpu.ac = tmp as u8;
+fn adc(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
-	1
+	add_with_carry(cpu, cpu.fetched)
 }
-fn and(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn and(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	cpu.ac &= cpu.fetched;
-	cpu.set_flag('Z', cpu.ac == 0);
-	cpu.set_flag('N', (cpu.ac & 0x80) != 0);
+	cpu.sr.set_zn(cpu.ac);
 
-	1
+	0
 }
-fn asl(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn asl(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp: u16 = (cpu.fetched as u16) << 1;
-	cpu.set_flag('C', (cpu.fetched & 0x80) != 0);
-	cpu.set_flag('Z', (tmp & 0x00FF) == 0);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set(Status::C, (cpu.fetched & 0x80) != 0);
+	cpu.sr.set_zn(tmp as u8);
 
-	if LOOK_UP[cpu.opcode as usize].address_mode == imp {
+	if cpu.address_mode() == AddressingMode::Accumulator {
 		cpu.ac = tmp as u8;
 	} else {
-		cpu.mem[cpu.addr_abs as usize] = tmp as u8;
+		bus.write(cpu.addr_abs, tmp as u8);
 	}
 
 	0
 }
-fn bcc(cpu: &mut Cpu) -> u8 {
-	if cpu.get_flag('C') == 0 {
-		cpu.addr_abs = cpu.pc + cpu.addr_rel;
-
-		cpu.cycles += 1;
-		if cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00 { cpu.cycles += 1; }
-
-		cpu.pc = cpu.addr_abs;
-	}
-
-	0
+fn bcc(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, !cpu.sr.contains(Status::C))
 }
-fn bcs(cpu: &mut Cpu) -> u8 {
-	if cpu.get_flag('C') == 1 {
-		cpu.addr_abs = cpu.pc + cpu.addr_rel;
-
-		cpu.cycles += 1;
-		if cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00 { cpu.cycles += 1; }
-
-		cpu.pc = cpu.addr_abs;
-	}
-
-	0
+fn bcs(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, cpu.sr.contains(Status::C))
 }
-fn beq(cpu: &mut Cpu) -> u8 {
-	if cpu.get_flag('Z') == 1 {
-		cpu.addr_abs = cpu.pc + cpu.addr_rel;
-
-		cpu.cycles += 1;
-		if cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00 { cpu.cycles += 1; }
-
-		cpu.pc = cpu.addr_abs;
-	}
-
-	0
+fn beq(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, cpu.sr.contains(Status::Z))
 }
-fn bit(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn bit(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.ac & cpu.fetched;
-	cpu.set_flag('N', (cpu.fetched & 0x80) != 0);
-	cpu.set_flag('V', (cpu.fetched & 0x40) != 0);
-	cpu.set_flag('Z', (tmp & 0x00FF) == 0);
+	cpu.sr.set(Status::Z, tmp == 0);
 
-	0
-}
-fn bmi(cpu: &mut Cpu) -> u8 {
-	if cpu.get_flag('N') == 1 {
-		cpu.addr_abs = cpu.pc + cpu.addr_rel;
-
-		cpu.cycles += 1;
-		if cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00 { cpu.cycles += 1; }
-
-		cpu.pc = cpu.addr_abs;
+	// 65C02 BIT # only sets Z
+	if cpu.address_mode() != AddressingMode::Immediate {
+		cpu.sr.set(Status::N, (cpu.fetched & 0x80) != 0);
+		cpu.sr.set(Status::V, (cpu.fetched & 0x40) != 0);
 	}
 
 	0
 }
-fn bne(cpu: &mut Cpu) -> u8 {
-	if cpu.get_flag('Z') == 0 {
-		cpu.addr_abs = cpu.pc + cpu.addr_rel;
-
-		cpu.cycles += 1;
-		if cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00 { cpu.cycles += 1; }
-
-		cpu.pc = cpu.addr_abs;
-	}
-
-	0
+fn bmi(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, cpu.sr.contains(Status::N))
 }
-fn bpl(cpu: &mut Cpu) -> u8 {
-	if cpu.get_flag('N') == 0 {
-		cpu.addr_abs = cpu.pc + cpu.addr_rel;
-
-		cpu.cycles += 1;
-		if cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00 { cpu.cycles += 1; }
-
-		cpu.pc = cpu.addr_abs;
-	}
-
-	0
+fn bne(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, !cpu.sr.contains(Status::Z))
 }
-fn brk(cpu: &mut Cpu) -> u8 {
-	cpu.pc += 1;
-	
-	cpu.mem[0x0100 + cpu.sp as usize] = ((cpu.pc & 0xFF00) >> 8) as u8;
-	cpu.sp = cpu.sp.overflowing_sub(1).0;
-	cpu.mem[0x0100 + cpu.sp as usize] = (cpu.pc & 0x00FF) as u8;
-	cpu.sp = cpu.sp.overflowing_sub(1).0;
-
-	cpu.set_flag('B', true);
-	cpu.mem[0x0100 + cpu.sp as usize] = cpu.sr;
-	cpu.sp = cpu.sp.overflowing_sub(1).0;
-	cpu.set_flag('B', false);
-
-	cpu.pc = cpu.mem[0xFFFE] as u16 | ((cpu.mem[0xFFFF] as u16) << 8);
-
-	0
+fn bpl(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, !cpu.sr.contains(Status::N))
 }
-fn bvc(cpu: &mut Cpu) -> u8 {
-	if cpu.get_flag('V') == 0 {
-		cpu.addr_abs = cpu.pc + cpu.addr_rel;
-
-		cpu.cycles += 1;
-		if cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00 { cpu.cycles += 1; }
-
-		cpu.pc = cpu.addr_abs;
-	}
+fn brk(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.pc = cpu.pc.wrapping_add(1);
+	cpu.interrupt(bus, 0xFFFE, true);
 
 	0
 }
-fn bvs(cpu: &mut Cpu) -> u8 {
-	if cpu.get_flag('V') == 1 {
-		cpu.addr_abs = cpu.pc + cpu.addr_rel;
-
-		cpu.cycles += 1;
-		if cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00 { cpu.cycles += 1; }
-
-		cpu.pc = cpu.addr_abs;
-	}
-
-	0
+fn bvc(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, !cpu.sr.contains(Status::V))
+}
+fn bvs(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, cpu.sr.contains(Status::V))
 }
-fn clc(cpu: &mut Cpu) -> u8 {
-	cpu.set_flag('C', false);
+fn clc(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.sr.set(Status::C, false);
 
 	0
 }
-fn cld(cpu: &mut Cpu) -> u8 {
-	cpu.set_flag('D', false);
+fn cld(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.sr.set(Status::D, false);
 
 	0
 }
-fn cli(cpu: &mut Cpu) -> u8 {
-	cpu.set_flag('I', false);
+fn cli(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.delay_i(cpu.sr.contains(Status::I));
+	cpu.sr.set(Status::I, false);
 
 	0
 }
-fn clv(cpu: &mut Cpu) -> u8 {
-	cpu.set_flag('V', false);
+fn clv(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.sr.set(Status::V, false);
 
 	0
 }
-fn cmp(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn cmp(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.ac.overflowing_sub(cpu.fetched).0;
-	cpu.set_flag('C', cpu.ac >= cpu.fetched);
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set(Status::C, cpu.ac >= cpu.fetched);
+	cpu.sr.set_zn(tmp);
 
-	1
+	0
 }
-fn cpx(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn cpx(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.x.overflowing_sub(cpu.fetched).0;
-	cpu.set_flag('C', cpu.x >= cpu.fetched);
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set(Status::C, cpu.x >= cpu.fetched);
+	cpu.sr.set_zn(tmp);
 
 	0
 }
-fn cpy(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn cpy(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.y.overflowing_sub(cpu.fetched).0;
-	cpu.set_flag('C', cpu.y >= cpu.fetched);
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set(Status::C, cpu.y >= cpu.fetched);
+	cpu.sr.set_zn(tmp);
 
 	0
 }
-fn dec(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn dec(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.fetched.overflowing_sub(1).0;
-	cpu.mem[cpu.addr_abs as usize] = tmp;
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set_zn(tmp);
+
+	// 65C02 DEC A
+	if cpu.address_mode() == AddressingMode::Accumulator {
+		cpu.ac = tmp;
+	} else {
+		bus.write(cpu.addr_abs, tmp);
+	}
 
 	0
 }
-fn dex(cpu: &mut Cpu) -> u8 {
+fn dex(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	let tmp = cpu.x.overflowing_sub(1).0;
 	cpu.x = tmp;
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set_zn(tmp);
 
 	0
 }
-fn dey(cpu: &mut Cpu) -> u8 {
+fn dey(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	let tmp = cpu.y.overflowing_sub(1).0;
 	cpu.y = tmp;
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set_zn(tmp);
 
 	0
 }
-fn eor(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn eor(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.ac ^ cpu.fetched;
 	cpu.ac = tmp;
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set_zn(tmp);
 
-	1
+	0
 }
-fn inc(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn inc(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.fetched.overflowing_add(1).0;
-	cpu.mem[cpu.addr_abs as usize] = tmp;
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set_zn(tmp);
+
+	// 65C02 INC A
+	if cpu.address_mode() == AddressingMode::Accumulator {
+		cpu.ac = tmp;
+	} else {
+		bus.write(cpu.addr_abs, tmp);
+	}
 
 	0
 }
-fn inx(cpu: &mut Cpu) -> u8 {
+fn inx(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	let tmp = cpu.x.overflowing_add(1).0;
 	cpu.x = tmp;
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set_zn(tmp);
 
 	0
 }
-fn iny(cpu: &mut Cpu) -> u8 {
+fn iny(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	let tmp = cpu.y.overflowing_add(1).0;
 	cpu.y = tmp;
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set_zn(tmp);
 
 	0
 }
-fn jmp(cpu: &mut Cpu) -> u8 {
+fn jmp(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.pc = cpu.addr_abs;
 
 	0
 }
-fn jsr(cpu: &mut Cpu) -> u8 {
-	cpu.pc -= 1;
+fn jsr(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.pc = cpu.pc.wrapping_sub(1);
 
-	cpu.mem[0x0100 + cpu.sp as usize] = ((cpu.pc & 0xFF00) >> 8) as u8;
-	cpu.sp = cpu.sp.overflowing_sub(1).0;
-	cpu.mem[0x0100 + cpu.sp as usize] = (cpu.pc & 0x00FF) as u8;
-	cpu.sp = cpu.sp.overflowing_sub(1).0;
+	cpu.push(bus, ((cpu.pc & 0xFF00) >> 8) as u8);
+	cpu.push(bus, (cpu.pc & 0x00FF) as u8);
 
 	cpu.pc = cpu.addr_abs;
 
 	0
 }
-fn lda(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn lda(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	cpu.ac = cpu.fetched;
-	cpu.set_flag('Z', cpu.ac == 0x00);
-	cpu.set_flag('N', (cpu.ac & 0x80) != 0);
+	cpu.sr.set_zn(cpu.ac);
 
-	1
+	0
 }
-fn ldx(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn ldx(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	cpu.x = cpu.fetched;
-	cpu.set_flag('Z', cpu.x == 0x00);
-	cpu.set_flag('N', (cpu.x & 0x80) != 0);
+	cpu.sr.set_zn(cpu.x);
 
-	1
+	0
 }
-fn ldy(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn ldy(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	cpu.y = cpu.fetched;
-	cpu.set_flag('Z', cpu.y == 0x00);
-	cpu.set_flag('N', (cpu.y & 0x80) != 0);
+	cpu.sr.set_zn(cpu.y);
 
-	1
+	0
 }
-fn lsr(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn lsr(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.fetched >> 1;
-	cpu.set_flag('C', (cpu.fetched & 0x1) != 0);
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set(Status::C, (cpu.fetched & 0x1) != 0);
+	cpu.sr.set_zn(tmp);
 
-	if LOOK_UP[cpu.opcode as usize].address_mode == imp {
+	if cpu.address_mode() == AddressingMode::Accumulator {
 		cpu.ac = tmp;
 	} else {
-		cpu.mem[cpu.addr_abs as usize] = tmp;
+		bus.write(cpu.addr_abs, tmp);
 	}
 
 	0
 }
-fn nop(_: &mut Cpu) -> u8 { 0 }
-fn ora(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+// The unofficial NOPs with an operand still perform the read
+fn nop(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	0
+}
+fn ora(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
 	let tmp = cpu.ac | cpu.fetched;
 	cpu.ac = tmp;
-	cpu.set_flag('Z', tmp == 0x00);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	cpu.sr.set_zn(tmp);
 
-	1
+	0
 }
-fn pha(cpu: &mut Cpu) -> u8 {
-	cpu.mem[0x0100 + cpu.sp as usize] = cpu.ac;
-	cpu.sp = cpu.sp.overflowing_sub(1).0;
+fn pha(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.push(bus, cpu.ac);
 
 	0
 }
-fn php(cpu: &mut Cpu) -> u8 {
-	cpu.mem[0x0100 + cpu.sp as usize] = cpu.sr;
-	cpu.sp = cpu.sp.overflowing_sub(1).0;
+fn php(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.push(bus, cpu.status_to_push(true));
 
 	0
 }
-fn pla(cpu: &mut Cpu) -> u8 {
-	cpu.sp = cpu.sp.overflowing_add(1).0;
-	cpu.ac = cpu.mem[0x0100 + cpu.sp as usize];
-	cpu.set_flag('Z', cpu.ac == 0x00);
-	cpu.set_flag('N', (cpu.ac & 0x80) != 0);
+fn pla(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.ac = cpu.pull(bus);
+	cpu.sr.set_zn(cpu.ac);
 
 	0
 }
-fn plp(cpu: &mut Cpu) -> u8 {
-	cpu.sp = cpu.sp.overflowing_add(1).0;
-	cpu.sr = cpu.mem[0x0100 + cpu.sp as usize];
+fn plp(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.delay_i(cpu.sr.contains(Status::I));
+	cpu.pull_status(bus);
 
 	0
 }
-fn rol(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn rol(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
-	let tmp: u16 = ((cpu.fetched as u16) << 1) + cpu.get_flag('C') as u16;
-	cpu.set_flag('C', (cpu.fetched & 0x80) != 0);
-	cpu.set_flag('Z', (tmp & 0x00FF) == 0);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	let tmp: u16 = ((cpu.fetched as u16) << 1) + cpu.sr.carry() as u16;
+	cpu.sr.set(Status::C, (cpu.fetched & 0x80) != 0);
+	cpu.sr.set_zn(tmp as u8);
 
-	if LOOK_UP[cpu.opcode as usize].address_mode == imp {
+	if cpu.address_mode() == AddressingMode::Accumulator {
 		cpu.ac = tmp as u8;
 	} else {
-		cpu.mem[cpu.addr_abs as usize] = tmp as u8;
+		bus.write(cpu.addr_abs, tmp as u8);
 	}
 
 	0
 }
-fn ror(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
+fn ror(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
-	let tmp = (cpu.fetched >> 1) | (cpu.get_flag('C') * 0x80);
-	cpu.set_flag('C', (cpu.fetched & 0x1) != 0);
-	cpu.set_flag('Z', (tmp & 0x00FF) == 0);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
+	let tmp = (cpu.fetched >> 1) | (cpu.sr.carry() << 7);
+	cpu.sr.set(Status::C, (cpu.fetched & 0x1) != 0);
+	cpu.sr.set_zn(tmp);
 
-	if LOOK_UP[cpu.opcode as usize].address_mode == imp {
-		cpu.ac = tmp as u8;
+	if cpu.address_mode() == AddressingMode::Accumulator {
+		cpu.ac = tmp;
 	} else {
-		cpu.mem[cpu.addr_abs as usize] = tmp as u8;
+		bus.write(cpu.addr_abs, tmp);
 	}
 
 	0
 }
-fn rti(cpu: &mut Cpu) -> u8 {
-	cpu.sp = cpu.sp.overflowing_add(1).0;
-	cpu.sr = cpu.mem[0x0100 + cpu.sp as usize];
+fn rti(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.pull_status(bus);
 
-	cpu.sp = cpu.sp.overflowing_add(1).0;
-	cpu.pc = cpu.mem[0x0100 + cpu.sp as usize] as u16;
-	cpu.sp = cpu.sp.overflowing_add(1).0;
-	cpu.pc |= (cpu.mem[0x0100 + cpu.sp as usize] as u16) << 8;
+	cpu.pc = cpu.pull(bus) as u16;
+	cpu.pc |= (cpu.pull(bus) as u16) << 8;
 
 	0
 }
-fn rts(cpu: &mut Cpu) -> u8 {
-	cpu.sp = cpu.sp.overflowing_add(1).0;
-	cpu.pc = cpu.mem[0x0100 + cpu.sp as usize] as u16;
-	cpu.sp = cpu.sp.overflowing_add(1).0;
-	cpu.pc |= (cpu.mem[0x0100 + cpu.sp as usize] as u16) << 8;
+fn rts(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.pc = cpu.pull(bus) as u16;
+	cpu.pc |= (cpu.pull(bus) as u16) << 8;
 
-	cpu.pc += 1;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
 	0
 }
-fn sbc(cpu: &mut Cpu) -> u8 {
-	cpu.fetch();
-
-	let val: u16 = cpu.fetched as u16 ^ 0x00FF;
-	let tmp: u16 = cpu.ac as u16 + val + cpu.get_flag('C') as u16;
-	cpu.set_flag('C', tmp > 255);
-	cpu.set_flag('Z', (tmp & 0x00FF) == 0);
-	cpu.set_flag('N', (tmp & 0x80) != 0);
-	cpu.set_flag('V', ((tmp & cpu.ac as u16) & (tmp ^ val) & 0x0080) != 0);
-
-	cpu.ac = tmp as u8;
+fn sbc(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
 
-	1
+	subtract_with_borrow(cpu, cpu.fetched)
 }
-fn sec(cpu: &mut Cpu) -> u8 {
-	cpu.set_flag('C', true);
+fn sec(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.sr.set(Status::C, true);
 
 	0
 }
-fn sed(cpu: &mut Cpu) -> u8 {
-	cpu.set_flag('D', true);
+fn sed(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.sr.set(Status::D, true);
 
 	0
 }
-fn sei(cpu: &mut Cpu) -> u8 {
-	cpu.set_flag('I', true);
+fn sei(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.delay_i(cpu.sr.contains(Status::I));
+	cpu.sr.set(Status::I, true);
 
 	0
 }
-fn sta(cpu: &mut Cpu) -> u8 {
-	cpu.mem[cpu.addr_abs as usize] = cpu.ac;
+fn sta(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	bus.write(cpu.addr_abs, cpu.ac);
 
 	0
 }
-fn stx(cpu: &mut Cpu) -> u8 {
-	cpu.mem[cpu.addr_abs as usize] = cpu.x;
+fn stx(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	bus.write(cpu.addr_abs, cpu.x);
 
 	0
 }
-fn sty(cpu: &mut Cpu) -> u8 {
-	cpu.mem[cpu.addr_abs as usize] = cpu.y;
+fn sty(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	bus.write(cpu.addr_abs, cpu.y);
 
 	0
 }
-fn tax(cpu: &mut Cpu) -> u8 {
+fn tax(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.x = cpu.ac;
-	cpu.set_flag('Z', cpu.x == 0);
-	cpu.set_flag('N', (cpu.x & 0x80) != 0);
+	cpu.sr.set_zn(cpu.x);
 
 	0
 }
-fn tay(cpu: &mut Cpu) -> u8 {
+fn tay(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.y = cpu.ac;
-	cpu.set_flag('Z', cpu.y == 0);
-	cpu.set_flag('N', (cpu.y & 0x80) != 0);
+	cpu.sr.set_zn(cpu.y);
 
 	0
 }
-fn tsx(cpu: &mut Cpu) -> u8 {
+fn tsx(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.x = cpu.sp;
-	cpu.set_flag('Z', cpu.x == 0);
-	cpu.set_flag('N', (cpu.x & 0x80) != 0);
+	cpu.sr.set_zn(cpu.x);
 
 	0
 }
-fn txa(cpu: &mut Cpu) -> u8 {
+fn txa(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.ac = cpu.x;
-	cpu.set_flag('Z', cpu.ac == 0);
-	cpu.set_flag('N', (cpu.ac & 0x80) != 0);
+	cpu.sr.set_zn(cpu.ac);
 
 	0
 }
-fn txs(cpu: &mut Cpu) -> u8 {
+fn txs(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.sp = cpu.x;
 
 	0
 }
-fn tya(cpu: &mut Cpu) -> u8 {
+fn tya(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.ac = cpu.y;
-	cpu.set_flag('Z', cpu.y == 0);
-	cpu.set_flag('N', (cpu.y & 0x80) != 0);
+	cpu.sr.set_zn(cpu.y);
 
 	0
 }
-fn xxx(_: &mut Cpu) -> u8 { 0 }
+
+
+
+//
+// Unofficial Opcodes
+//	https://www.nesdev.org/wiki/CPU_unofficial_opcodes
+//	https://www.masswerk.at/nowgobang/2021/6502-illegal-opcodes
+
+// JAM/KIL: the CPU stops fetching and the bus sits at $FFFF until reset
+fn jam(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.pc = cpu.pc.wrapping_sub(1);
+	cpu.halt(cpu.pc);
+
+	0
+}
+fn alr(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = cpu.ac & cpu.fetched;
+	cpu.sr.set(Status::C, (tmp & 0x01) != 0);
+	cpu.ac = tmp >> 1;
+	cpu.sr.set_zn(cpu.ac);
+
+	0
+}
+fn anc(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	cpu.ac &= cpu.fetched;
+	cpu.sr.set_zn(cpu.ac);
+	cpu.sr.set(Status::C, (cpu.ac & 0x80) != 0);
+
+	0
+}
+fn arr(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let and = cpu.ac & cpu.fetched;
+	let tmp = (and >> 1) | (cpu.sr.carry() << 7);
+	cpu.ac = tmp;
+	cpu.sr.set_zn(tmp);
+
+	if cpu.decimal() {
+		// NMOS decimal mode fixes up each digit of the rotated value
+		//	https://www.nesdev.org/6502_cpu.txt
+		let (hi, lo) = (and >> 4, and & 0x0F);
+		cpu.sr.set(Status::V, (and ^ tmp) & 0x40 != 0);
+		if lo + (lo & 0x01) > 5 {
+			cpu.ac = (cpu.ac & 0xF0) | (cpu.ac.wrapping_add(6) & 0x0F);
+		}
+		cpu.sr.set(Status::C, hi + (hi & 0x01) > 5);
+		if cpu.sr.contains(Status::C) {
+			cpu.ac = cpu.ac.wrapping_add(0x60);
+		}
+		return 0;
+	}
+
+	cpu.sr.set(Status::C, (tmp & 0x40) != 0);
+	cpu.sr.set(Status::V, ((tmp >> 6) ^ (tmp >> 5)) & 0x01 != 0);
+
+	0
+}
+fn axs(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let ax = cpu.ac & cpu.x;
+	cpu.x = ax.wrapping_sub(cpu.fetched);
+	cpu.sr.set(Status::C, ax >= cpu.fetched);
+	cpu.sr.set_zn(cpu.x);
+
+	0
+}
+fn dcp(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = cpu.fetched.wrapping_sub(1);
+	bus.write(cpu.addr_abs, tmp);
+	compare(cpu, cpu.ac, tmp);
+
+	0
+}
+fn isc(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = cpu.fetched.wrapping_add(1);
+	bus.write(cpu.addr_abs, tmp);
+	subtract_with_borrow(cpu, tmp);
+
+	0
+}
+fn las(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = cpu.fetched & cpu.sp;
+	cpu.ac = tmp;
+	cpu.x = tmp;
+	cpu.sp = tmp;
+	cpu.sr.set_zn(tmp);
+
+	0
+}
+fn lax(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	cpu.ac = cpu.fetched;
+	cpu.x = cpu.fetched;
+	cpu.sr.set_zn(cpu.ac);
+
+	0
+}
+// LXA (#$AB): unstable, the accumulator is ORed with a chip dependent
+// magic constant first. $EE matches most 2A03s and the test suites.
+fn lxa(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = (cpu.ac | UNSTABLE_MAGIC) & cpu.fetched;
+	cpu.ac = tmp;
+	cpu.x = tmp;
+	cpu.sr.set_zn(tmp);
+
+	0
+}
+fn rla(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = (cpu.fetched << 1) | cpu.sr.carry();
+	cpu.sr.set(Status::C, (cpu.fetched & 0x80) != 0);
+	bus.write(cpu.addr_abs, tmp);
+
+	cpu.ac &= tmp;
+	cpu.sr.set_zn(cpu.ac);
+
+	0
+}
+fn rra(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = (cpu.fetched >> 1) | (cpu.sr.carry() << 7);
+	cpu.sr.set(Status::C, (cpu.fetched & 0x01) != 0);
+	bus.write(cpu.addr_abs, tmp);
+	add_with_carry(cpu, tmp);
+
+	0
+}
+fn sax(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	bus.write(cpu.addr_abs, cpu.ac & cpu.x);
+
+	0
+}
+fn sha(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	store_high_and(cpu, bus, cpu.ac & cpu.x, cpu.y);
+
+	0
+}
+fn shx(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	store_high_and(cpu, bus, cpu.x, cpu.y);
+
+	0
+}
+fn shy(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	store_high_and(cpu, bus, cpu.y, cpu.x);
+
+	0
+}
+fn slo(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = cpu.fetched << 1;
+	cpu.sr.set(Status::C, (cpu.fetched & 0x80) != 0);
+	bus.write(cpu.addr_abs, tmp);
+
+	cpu.ac |= tmp;
+	cpu.sr.set_zn(cpu.ac);
+
+	0
+}
+fn sre(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = cpu.fetched >> 1;
+	cpu.sr.set(Status::C, (cpu.fetched & 0x01) != 0);
+	bus.write(cpu.addr_abs, tmp);
+
+	cpu.ac ^= tmp;
+	cpu.sr.set_zn(cpu.ac);
+
+	0
+}
+fn tas(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.sp = cpu.ac & cpu.x;
+	store_high_and(cpu, bus, cpu.sp, cpu.y);
+
+	0
+}
+// XAA/ANE (#$8B): unstable, see lxa
+fn xaa(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	cpu.ac = (cpu.ac | UNSTABLE_MAGIC) & cpu.x & cpu.fetched;
+	cpu.sr.set_zn(cpu.ac);
+
+	0
+}
+
+const UNSTABLE_MAGIC: u8 = 0xEE;
+
+
+
+//
+// Helpers
+
+// ADC, in BCD when the chip has decimal mode and D is set
+// Both return the extra cycle the 65C02 spends in decimal mode
+fn add_with_carry(cpu: &mut Cpu, value: u8) -> u8 {
+	match cpu.decimal() {
+		false => add(cpu, value),
+		true if cpu.variant == Variant::Wdc65C02 => { cmos::add_decimal_cmos(cpu, value); return 1; }
+		true => add_decimal(cpu, value),
+	}
+
+	0
+}
+
+fn subtract_with_borrow(cpu: &mut Cpu, value: u8) -> u8 {
+	match cpu.decimal() {
+		false => add(cpu, value ^ 0xFF),
+		true if cpu.variant == Variant::Wdc65C02 => { cmos::subtract_decimal_cmos(cpu, value); return 1; }
+		true => subtract_decimal(cpu, value),
+	}
+
+	0
+}
+
+// Takes the branch when condition holds: one extra cycle, or two when the
+// target is on another page than the instruction that follows
+fn branch(cpu: &mut Cpu, condition: bool) -> u8 {
+	if !condition {
+		return 0;
+	}
+
+	cpu.addr_abs = cpu.pc.wrapping_add(cpu.addr_rel);
+	let crossed = cpu.addr_abs & 0xFF00 != cpu.pc & 0xFF00;
+	cpu.pc = cpu.addr_abs;
+
+	1 + crossed as u8
+}
+
+fn add(cpu: &mut Cpu, value: u8) {
+	let tmp: u16 = cpu.ac as u16 + value as u16 + cpu.sr.carry() as u16;
+	cpu.sr.set(Status::C, tmp > 255);
+	cpu.sr.set_zn(tmp as u8);
+	// Overflow when both inputs share a sign the result does not have
+	cpu.sr.set(Status::V, ((tmp ^ cpu.ac as u16) & (tmp ^ value as u16) & 0x0080) != 0);
+
+	cpu.ac = tmp as u8;
+}
+
+// NMOS BCD addition. Z comes from the binary sum, N and V from the result
+// before the high digit is adjusted, as on the real chip.
+//	http://www.6502.org/tutorials/decimal_mode.html
+fn add_decimal(cpu: &mut Cpu, value: u8) {
+	let (a, m, c) = (cpu.ac as u16, value as u16, cpu.sr.carry() as u16);
+
+	let mut lo = (a & 0x0F) + (m & 0x0F) + c;
+	if lo > 0x09 {
+		lo += 0x06;
+	}
+	let mut tmp = (lo & 0x0F) + (a & 0xF0) + (m & 0xF0) + if lo > 0x0F { 0x10 } else { 0 };
+
+	cpu.sr.set(Status::Z, (a + m + c) & 0xFF == 0);
+	cpu.sr.set(Status::N, tmp & 0x80 != 0);
+	cpu.sr.set(Status::V, (a ^ tmp) & 0x80 != 0 && (a ^ m) & 0x80 == 0);
+
+	if tmp & 0x1F0 > 0x90 {
+		tmp += 0x60;
+	}
+	cpu.sr.set(Status::C, tmp & 0xFF0 > 0xF0);
+
+	cpu.ac = tmp as u8;
+}
+
+// NMOS BCD subtraction. Flags are exactly those of the binary subtraction.
+fn subtract_decimal(cpu: &mut Cpu, value: u8) {
+	let (a, m) = (cpu.ac as i16, value as i16);
+	let borrow = 1 - cpu.sr.carry() as i16;
+
+	let mut lo = (a & 0x0F) - (m & 0x0F) - borrow;
+	let mut hi = (a >> 4) - (m >> 4);
+	if lo < 0 {
+		lo -= 6;
+		hi -= 1;
+	}
+	if hi < 0 {
+		hi -= 6;
+	}
+
+	add(cpu, value ^ 0xFF);
+	cpu.ac = ((hi << 4) | (lo & 0x0F)) as u8;
+}
+
+fn compare(cpu: &mut Cpu, reg: u8, value: u8) {
+	let tmp = reg.wrapping_sub(value);
+	cpu.sr.set(Status::C, reg >= value);
+	cpu.sr.set_zn(tmp);
+}
+
+// SHA/SHX/SHY/TAS store value & (H + 1), H being the high byte of the base
+// address before indexing. If indexing crossed a page, the stored value
+// also replaces the high byte of the target address.
+fn store_high_and(cpu: &mut Cpu, bus: &mut dyn Bus, value: u8, index: u8) {
+	let base = cpu.addr_abs.wrapping_sub(index as u16);
+	let data = value & ((base >> 8) as u8).wrapping_add(1);
+
+	let addr = if base & 0xFF00 != cpu.addr_abs & 0xFF00 {
+		((data as u16) << 8) | (cpu.addr_abs & 0x00FF)
+	} else {
+		cpu.addr_abs
+	};
+
+	bus.write(addr, data);
+}
 
 
 
 //
 // Address Modes
 
-fn abs(cpu: &mut Cpu) -> u8 {
-	let lo = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
-	let hi = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
+fn abs(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let lo = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
+	let hi = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
 	cpu.addr_abs = (hi << 8) | lo;
 
 	0
 }
 
-fn abx(cpu: &mut Cpu) -> u8 {
-	let lo = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
-	let hi = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
+fn abx(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let lo = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
+	let hi = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
-	cpu.addr_abs = ((hi << 8) | lo) + cpu.x as u16;
+	cpu.addr_abs = ((hi << 8) | lo).wrapping_add(cpu.x as u16);
 
 	if cpu.addr_abs & 0xFF00 != hi << 8 { return 1; }
 
 	0
 }	
 
-fn aby(cpu: &mut Cpu) -> u8 {
-	let lo = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
-	let hi = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
+fn aby(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let lo = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
+	let hi = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
-	cpu.addr_abs = ((hi << 8) | lo) + cpu.y as u16;
+	cpu.addr_abs = ((hi << 8) | lo).wrapping_add(cpu.y as u16);
 
 	if cpu.addr_abs & 0xFF00 != hi << 8 { return 1; }
 
@@ -562,61 +833,67 @@ fn aby(cpu: &mut Cpu) -> u8 {
 }
 
 
-fn imm(cpu: &mut Cpu) -> u8 {
+fn imm(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.addr_abs = cpu.pc;
-	cpu.pc += 1;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
 	0
 }
 
-pub fn imp(cpu: &mut Cpu) -> u8 {
+fn imp(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
 	cpu.fetched = cpu.ac;
 
 	0
 }
 
-fn ind(cpu: &mut Cpu) -> u8 {
-	let lo = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
-	let hi = cpu.mem[cpu.pc as usize +1] as u16;
-	cpu.pc += 1;	
+fn ind(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let lo = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
+	let hi = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
 	let tmp = (hi << 8) | lo;
 
-	cpu.addr_abs = ((cpu.mem[tmp as usize + 1] as u16) << 8) | cpu.mem[tmp as usize] as u16;
+	// Page boundary bug: the pointer's high byte comes from the start of
+	// the same page, so JMP ($10FF) reads $10FF and $1000
+	let tmp_hi = (tmp & 0xFF00) | (tmp.wrapping_add(1) & 0x00FF);
+
+	cpu.addr_abs = ((bus.read(tmp_hi) as u16) << 8) | bus.read(tmp) as u16;
 
 	0
 }
 
-fn xid(cpu: &mut Cpu) -> u8 {
-	let tmp = cpu.x.wrapping_add(cpu.mem[cpu.pc as usize]) as u16;
-	cpu.pc += 1;
+fn xid(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let tmp = cpu.x.wrapping_add(bus.read(cpu.pc)) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
-	let lo = cpu.mem[tmp as usize] as u16;
-	let hi = cpu.mem[tmp as usize +1] as u16;
+	// The pointer wraps within the zero page
+	let lo = bus.read(tmp) as u16;
+	let hi = bus.read((tmp + 1) & 0x00FF) as u16;
 
 	cpu.addr_abs = (hi << 8) | lo;
 
 	0
 }
 
-fn idy(cpu: &mut Cpu) -> u8 {
-	let tmp = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
+fn idy(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let tmp = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
-	let lo = cpu.mem[tmp as usize] as u16;
-	let hi = cpu.mem[tmp as usize +1] as u16;
+	// The pointer wraps within the zero page
+	let lo = bus.read(tmp) as u16;
+	let hi = bus.read((tmp + 1) & 0x00FF) as u16;
 
-	cpu.addr_abs = ((hi << 8) | lo) + cpu.y as u16;
+	cpu.addr_abs = ((hi << 8) | lo).wrapping_add(cpu.y as u16);
 
 	if cpu.addr_abs & 0xFF00 != hi << 8 { return 1; }
 
 	0
 }
 
-fn rel(cpu: &mut Cpu) -> u8 {
-	cpu.addr_rel = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
+fn rel(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.addr_rel = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
 	if cpu.addr_rel & 0x80 != 0 {
 		cpu.addr_rel |= 0xFF00;
@@ -625,42 +902,289 @@ fn rel(cpu: &mut Cpu) -> u8 {
 	0
 }
 
-fn zpg(cpu: &mut Cpu) -> u8 {
-	cpu.addr_abs = cpu.mem[cpu.pc as usize] as u16;
-	cpu.pc += 1;
+fn zpg(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.addr_abs = bus.read(cpu.pc) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 	
 	0
 }
 
-fn zpx(cpu: &mut Cpu) -> u8 {
-	cpu.addr_abs = cpu.x.wrapping_add(cpu.mem[cpu.pc as usize]) as u16;
-	cpu.pc += 1;
+fn zpx(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.addr_abs = cpu.x.wrapping_add(bus.read(cpu.pc)) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
 	0
 }
 
-fn zpy(cpu: &mut Cpu) -> u8 {
-	cpu.addr_abs = cpu.y.wrapping_add(cpu.mem[cpu.pc as usize]) as u16;
-	cpu.pc += 1;
+fn zpy(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.addr_abs = cpu.y.wrapping_add(bus.read(cpu.pc)) as u16;
+	cpu.pc = cpu.pc.wrapping_add(1);
 
 	0
 }
 
+const fn op(mnemonic: &'static str, instruction: fn(&mut Cpu, &mut dyn Bus) -> u8, address_mode: AddressingMode, cycles: u8, official: bool, page_penalty: bool) -> Opcodes {
+	Opcodes { mnemonic, instruction, address_mode, cycles, bytes: address_mode.bytes(), official, page_penalty }
+}
+
+use AddressingMode::*;
+
 pub const LOOK_UP: [Opcodes; 256] = [
-	Opcodes { instruction: brk, address_mode: imp, cycles: 7 }, Opcodes { instruction: ora, address_mode: xid, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: ora, address_mode: zpg, cycles: 3 }, Opcodes { instruction: asl, address_mode: zpg, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: php, address_mode: imp, cycles: 3 }, Opcodes { instruction: ora, address_mode: imm, cycles: 2 }, Opcodes { instruction: asl, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: ora, address_mode: abs, cycles: 4 }, Opcodes { instruction: asl, address_mode: abs, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: bpl, address_mode: rel, cycles: 2 }, Opcodes { instruction: ora, address_mode: idy, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: ora, address_mode: zpx, cycles: 4 }, Opcodes { instruction: asl, address_mode: zpx, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: clc, address_mode: imp, cycles: 2 }, Opcodes { instruction: ora, address_mode: aby, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: ora, address_mode: abx, cycles: 4 }, Opcodes { instruction: asl, address_mode: abx, cycles: 7 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: jsr, address_mode: abs, cycles: 6 }, Opcodes { instruction: and, address_mode: xid, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: bit, address_mode: zpg, cycles: 3 }, Opcodes { instruction: and, address_mode: zpg, cycles: 3 }, Opcodes { instruction: rol, address_mode: zpg, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: plp, address_mode: imp, cycles: 4 }, Opcodes { instruction: and, address_mode: imm, cycles: 2 }, Opcodes { instruction: rol, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: bit, address_mode: abs, cycles: 4 }, Opcodes { instruction: and, address_mode: abs, cycles: 4 }, Opcodes { instruction: rol, address_mode: abs, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: bmi, address_mode: rel, cycles: 2 }, Opcodes { instruction: and, address_mode: idy, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: and, address_mode: zpx, cycles: 4 }, Opcodes { instruction: rol, address_mode: zpx, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sec, address_mode: imp, cycles: 2 }, Opcodes { instruction: and, address_mode: aby, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: and, address_mode: abx, cycles: 4 }, Opcodes { instruction: rol, address_mode: abx, cycles: 7 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: rti, address_mode: imp, cycles: 6 }, Opcodes { instruction: eor, address_mode: xid, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: eor, address_mode: zpg, cycles: 3 }, Opcodes { instruction: lsr, address_mode: zpg, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: pha, address_mode: imp, cycles: 3 }, Opcodes { instruction: eor, address_mode: imm, cycles: 2 }, Opcodes { instruction: lsr, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: jmp, address_mode: abs, cycles: 3 }, Opcodes { instruction: eor, address_mode: abs, cycles: 4 }, Opcodes { instruction: lsr, address_mode: abs, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: bvc, address_mode: rel, cycles: 2 }, Opcodes { instruction: eor, address_mode: idy, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: eor, address_mode: zpx, cycles: 4 }, Opcodes { instruction: lsr, address_mode: zpx, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: cli, address_mode: imp, cycles: 2 }, Opcodes { instruction: eor, address_mode: aby, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: eor, address_mode: abx, cycles: 4 }, Opcodes { instruction: lsr, address_mode: abx, cycles: 7 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: rts, address_mode: imp, cycles: 6 }, Opcodes { instruction: adc, address_mode: xid, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: adc, address_mode: zpg, cycles: 3 }, Opcodes { instruction: ror, address_mode: zpg, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: pla, address_mode: imp, cycles: 4 }, Opcodes { instruction: adc, address_mode: imm, cycles: 2 }, Opcodes { instruction: ror, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: jmp, address_mode: ind, cycles: 5 }, Opcodes { instruction: adc, address_mode: abs, cycles: 4 }, Opcodes { instruction: ror, address_mode: abs, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: bvs, address_mode: rel, cycles: 2 }, Opcodes { instruction: adc, address_mode: idy, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: adc, address_mode: zpx, cycles: 4 }, Opcodes { instruction: ror, address_mode: zpx, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sei, address_mode: imp, cycles: 2 }, Opcodes { instruction: adc, address_mode: aby, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: adc, address_mode: abx, cycles: 4 }, Opcodes { instruction: ror, address_mode: abx, cycles: 7 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sta, address_mode: xid, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sty, address_mode: zpg, cycles: 3 }, Opcodes { instruction: sta, address_mode: zpg, cycles: 3 }, Opcodes { instruction: stx, address_mode: zpg, cycles: 3 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: dey, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: txa, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sty, address_mode: abs, cycles: 4 }, Opcodes { instruction: sta, address_mode: abs, cycles: 4 }, Opcodes { instruction: stx, address_mode: abs, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: bcc, address_mode: rel, cycles: 2 }, Opcodes { instruction: sta, address_mode: idy, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sty, address_mode: zpx, cycles: 4 }, Opcodes { instruction: sta, address_mode: zpx, cycles: 4 }, Opcodes { instruction: stx, address_mode: zpy, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: tya, address_mode: imp, cycles: 2 }, Opcodes { instruction: sta, address_mode: aby, cycles: 5 }, Opcodes { instruction: txs, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sta, address_mode: abx, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: ldy, address_mode: imm, cycles: 2 }, Opcodes { instruction: lda, address_mode: xid, cycles: 6 }, Opcodes { instruction: ldx, address_mode: imm, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: ldy, address_mode: zpg, cycles: 3 }, Opcodes { instruction: lda, address_mode: zpg, cycles: 3 }, Opcodes { instruction: ldx, address_mode: zpg, cycles: 3 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: tay, address_mode: imp, cycles: 2 }, Opcodes { instruction: lda, address_mode: imm, cycles: 2 }, Opcodes { instruction: tax, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: ldy, address_mode: abs, cycles: 4 }, Opcodes { instruction: lda, address_mode: abs, cycles: 4 }, Opcodes { instruction: ldx, address_mode: abs, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: bcs, address_mode: rel, cycles: 2 }, Opcodes { instruction: lda, address_mode: idy, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: ldy, address_mode: zpx, cycles: 4 }, Opcodes { instruction: lda, address_mode: zpx, cycles: 4 }, Opcodes { instruction: ldx, address_mode: zpy, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: clv, address_mode: imp, cycles: 2 }, Opcodes { instruction: lda, address_mode: aby, cycles: 4 }, Opcodes { instruction: tsx, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: ldy, address_mode: abx, cycles: 4 }, Opcodes { instruction: lda, address_mode: abx, cycles: 4 }, Opcodes { instruction: ldx, address_mode: aby, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: cpy, address_mode: imm, cycles: 2 }, Opcodes { instruction: cmp, address_mode: xid, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: cpy, address_mode: zpg, cycles: 3 }, Opcodes { instruction: cmp, address_mode: zpg, cycles: 3 }, Opcodes { instruction: dec, address_mode: zpg, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: iny, address_mode: imp, cycles: 2 }, Opcodes { instruction: cmp, address_mode: imm, cycles: 2 }, Opcodes { instruction: dex, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: cpy, address_mode: abs, cycles: 4 }, Opcodes { instruction: cmp, address_mode: abs, cycles: 4 }, Opcodes { instruction: dec, address_mode: abs, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: bne, address_mode: rel, cycles: 2 }, Opcodes { instruction: cmp, address_mode: idy, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: cmp, address_mode: zpx, cycles: 4 }, Opcodes { instruction: dec, address_mode: zpx, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: cld, address_mode: imp, cycles: 2 }, Opcodes { instruction: cmp, address_mode: aby, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: cmp, address_mode: abx, cycles: 4 }, Opcodes { instruction: dec, address_mode: abx, cycles: 7 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: cpx, address_mode: imm, cycles: 2 }, Opcodes { instruction: sbc, address_mode: xid, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: cpx, address_mode: zpg, cycles: 3 }, Opcodes { instruction: sbc, address_mode: zpg, cycles: 3 }, Opcodes { instruction: inc, address_mode: zpg, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: inx, address_mode: imp, cycles: 2 }, Opcodes { instruction: sbc, address_mode: imm, cycles: 2 }, Opcodes { instruction: nop, address_mode: imp, cycles: 2 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: cpx, address_mode: abs, cycles: 4 }, Opcodes { instruction: sbc, address_mode: abs, cycles: 4 }, Opcodes { instruction: inc, address_mode: abs, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
-	Opcodes { instruction: beq, address_mode: rel, cycles: 2 }, Opcodes { instruction: sbc, address_mode: idy, cycles: 5 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sbc, address_mode: zpx, cycles: 4 }, Opcodes { instruction: inc, address_mode: zpx, cycles: 6 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sed, address_mode: imp, cycles: 2 }, Opcodes { instruction: sbc, address_mode: aby, cycles: 4 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 }, Opcodes { instruction: sbc, address_mode: abx, cycles: 4 }, Opcodes { instruction: inc, address_mode: abx, cycles: 7 }, Opcodes { instruction: xxx, address_mode: imp, cycles: 0 },
+	// mnemonic, instruction, addressing mode, cycles, official, page penalty
+	op("BRK", brk, Implied, 7, true, false),     // 00
+	op("ORA", ora, IndirectX, 6, true, false),   // 01
+	op("JAM", jam, Implied, 0, false, false),    // 02
+	op("SLO", slo, IndirectX, 8, false, false),  // 03
+	op("NOP", nop, ZeroPage, 3, false, false),   // 04
+	op("ORA", ora, ZeroPage, 3, true, false),    // 05
+	op("ASL", asl, ZeroPage, 5, true, false),    // 06
+	op("SLO", slo, ZeroPage, 5, false, false),   // 07
+	op("PHP", php, Implied, 3, true, false),     // 08
+	op("ORA", ora, Immediate, 2, true, false),   // 09
+	op("ASL", asl, Accumulator, 2, true, false), // 0A
+	op("ANC", anc, Immediate, 2, false, false),  // 0B
+	op("NOP", nop, Absolute, 4, false, false),   // 0C
+	op("ORA", ora, Absolute, 4, true, false),    // 0D
+	op("ASL", asl, Absolute, 6, true, false),    // 0E
+	op("SLO", slo, Absolute, 6, false, false),   // 0F
+	op("BPL", bpl, Relative, 2, true, false),    // 10
+	op("ORA", ora, IndirectY, 5, true, true),    // 11
+	op("JAM", jam, Implied, 0, false, false),    // 12
+	op("SLO", slo, IndirectY, 8, false, false),  // 13
+	op("NOP", nop, ZeroPageX, 4, false, false),  // 14
+	op("ORA", ora, ZeroPageX, 4, true, false),   // 15
+	op("ASL", asl, ZeroPageX, 6, true, false),   // 16
+	op("SLO", slo, ZeroPageX, 6, false, false),  // 17
+	op("CLC", clc, Implied, 2, true, false),     // 18
+	op("ORA", ora, AbsoluteY, 4, true, true),    // 19
+	op("NOP", nop, Implied, 2, false, false),    // 1A
+	op("SLO", slo, AbsoluteY, 7, false, false),  // 1B
+	op("NOP", nop, AbsoluteX, 4, false, true),   // 1C
+	op("ORA", ora, AbsoluteX, 4, true, true),    // 1D
+	op("ASL", asl, AbsoluteX, 7, true, false),   // 1E
+	op("SLO", slo, AbsoluteX, 7, false, false),  // 1F
+	op("JSR", jsr, Absolute, 6, true, false),    // 20
+	op("AND", and, IndirectX, 6, true, false),   // 21
+	op("JAM", jam, Implied, 0, false, false),    // 22
+	op("RLA", rla, IndirectX, 8, false, false),  // 23
+	op("BIT", bit, ZeroPage, 3, true, false),    // 24
+	op("AND", and, ZeroPage, 3, true, false),    // 25
+	op("ROL", rol, ZeroPage, 5, true, false),    // 26
+	op("RLA", rla, ZeroPage, 5, false, false),   // 27
+	op("PLP", plp, Implied, 4, true, false),     // 28
+	op("AND", and, Immediate, 2, true, false),   // 29
+	op("ROL", rol, Accumulator, 2, true, false), // 2A
+	op("ANC", anc, Immediate, 2, false, false),  // 2B
+	op("BIT", bit, Absolute, 4, true, false),    // 2C
+	op("AND", and, Absolute, 4, true, false),    // 2D
+	op("ROL", rol, Absolute, 6, true, false),    // 2E
+	op("RLA", rla, Absolute, 6, false, false),   // 2F
+	op("BMI", bmi, Relative, 2, true, false),    // 30
+	op("AND", and, IndirectY, 5, true, true),    // 31
+	op("JAM", jam, Implied, 0, false, false),    // 32
+	op("RLA", rla, IndirectY, 8, false, false),  // 33
+	op("NOP", nop, ZeroPageX, 4, false, false),  // 34
+	op("AND", and, ZeroPageX, 4, true, false),   // 35
+	op("ROL", rol, ZeroPageX, 6, true, false),   // 36
+	op("RLA", rla, ZeroPageX, 6, false, false),  // 37
+	op("SEC", sec, Implied, 2, true, false),     // 38
+	op("AND", and, AbsoluteY, 4, true, true),    // 39
+	op("NOP", nop, Implied, 2, false, false),    // 3A
+	op("RLA", rla, AbsoluteY, 7, false, false),  // 3B
+	op("NOP", nop, AbsoluteX, 4, false, true),   // 3C
+	op("AND", and, AbsoluteX, 4, true, true),    // 3D
+	op("ROL", rol, AbsoluteX, 7, true, false),   // 3E
+	op("RLA", rla, AbsoluteX, 7, false, false),  // 3F
+	op("RTI", rti, Implied, 6, true, false),     // 40
+	op("EOR", eor, IndirectX, 6, true, false),   // 41
+	op("JAM", jam, Implied, 0, false, false),    // 42
+	op("SRE", sre, IndirectX, 8, false, false),  // 43
+	op("NOP", nop, ZeroPage, 3, false, false),   // 44
+	op("EOR", eor, ZeroPage, 3, true, false),    // 45
+	op("LSR", lsr, ZeroPage, 5, true, false),    // 46
+	op("SRE", sre, ZeroPage, 5, false, false),   // 47
+	op("PHA", pha, Implied, 3, true, false),     // 48
+	op("EOR", eor, Immediate, 2, true, false),   // 49
+	op("LSR", lsr, Accumulator, 2, true, false), // 4A
+	op("ALR", alr, Immediate, 2, false, false),  // 4B
+	op("JMP", jmp, Absolute, 3, true, false),    // 4C
+	op("EOR", eor, Absolute, 4, true, false),    // 4D
+	op("LSR", lsr, Absolute, 6, true, false),    // 4E
+	op("SRE", sre, Absolute, 6, false, false),   // 4F
+	op("BVC", bvc, Relative, 2, true, false),    // 50
+	op("EOR", eor, IndirectY, 5, true, true),    // 51
+	op("JAM", jam, Implied, 0, false, false),    // 52
+	op("SRE", sre, IndirectY, 8, false, false),  // 53
+	op("NOP", nop, ZeroPageX, 4, false, false),  // 54
+	op("EOR", eor, ZeroPageX, 4, true, false),   // 55
+	op("LSR", lsr, ZeroPageX, 6, true, false),   // 56
+	op("SRE", sre, ZeroPageX, 6, false, false),  // 57
+	op("CLI", cli, Implied, 2, true, false),     // 58
+	op("EOR", eor, AbsoluteY, 4, true, true),    // 59
+	op("NOP", nop, Implied, 2, false, false),    // 5A
+	op("SRE", sre, AbsoluteY, 7, false, false),  // 5B
+	op("NOP", nop, AbsoluteX, 4, false, true),   // 5C
+	op("EOR", eor, AbsoluteX, 4, true, true),    // 5D
+	op("LSR", lsr, AbsoluteX, 7, true, false),   // 5E
+	op("SRE", sre, AbsoluteX, 7, false, false),  // 5F
+	op("RTS", rts, Implied, 6, true, false),     // 60
+	op("ADC", adc, IndirectX, 6, true, false),   // 61
+	op("JAM", jam, Implied, 0, false, false),    // 62
+	op("RRA", rra, IndirectX, 8, false, false),  // 63
+	op("NOP", nop, ZeroPage, 3, false, false),   // 64
+	op("ADC", adc, ZeroPage, 3, true, false),    // 65
+	op("ROR", ror, ZeroPage, 5, true, false),    // 66
+	op("RRA", rra, ZeroPage, 5, false, false),   // 67
+	op("PLA", pla, Implied, 4, true, false),     // 68
+	op("ADC", adc, Immediate, 2, true, false),   // 69
+	op("ROR", ror, Accumulator, 2, true, false), // 6A
+	op("ARR", arr, Immediate, 2, false, false),  // 6B
+	op("JMP", jmp, Indirect, 5, true, false),    // 6C
+	op("ADC", adc, Absolute, 4, true, false),    // 6D
+	op("ROR", ror, Absolute, 6, true, false),    // 6E
+	op("RRA", rra, Absolute, 6, false, false),   // 6F
+	op("BVS", bvs, Relative, 2, true, false),    // 70
+	op("ADC", adc, IndirectY, 5, true, true),    // 71
+	op("JAM", jam, Implied, 0, false, false),    // 72
+	op("RRA", rra, IndirectY, 8, false, false),  // 73
+	op("NOP", nop, ZeroPageX, 4, false, false),  // 74
+	op("ADC", adc, ZeroPageX, 4, true, false),   // 75
+	op("ROR", ror, ZeroPageX, 6, true, false),   // 76
+	op("RRA", rra, ZeroPageX, 6, false, false),  // 77
+	op("SEI", sei, Implied, 2, true, false),     // 78
+	op("ADC", adc, AbsoluteY, 4, true, true),    // 79
+	op("NOP", nop, Implied, 2, false, false),    // 7A
+	op("RRA", rra, AbsoluteY, 7, false, false),  // 7B
+	op("NOP", nop, AbsoluteX, 4, false, true),   // 7C
+	op("ADC", adc, AbsoluteX, 4, true, true),    // 7D
+	op("ROR", ror, AbsoluteX, 7, true, false),   // 7E
+	op("RRA", rra, AbsoluteX, 7, false, false),  // 7F
+	op("NOP", nop, Immediate, 2, false, false),  // 80
+	op("STA", sta, IndirectX, 6, true, false),   // 81
+	op("NOP", nop, Immediate, 2, false, false),  // 82
+	op("SAX", sax, IndirectX, 6, false, false),  // 83
+	op("STY", sty, ZeroPage, 3, true, false),    // 84
+	op("STA", sta, ZeroPage, 3, true, false),    // 85
+	op("STX", stx, ZeroPage, 3, true, false),    // 86
+	op("SAX", sax, ZeroPage, 3, false, false),   // 87
+	op("DEY", dey, Implied, 2, true, false),     // 88
+	op("NOP", nop, Immediate, 2, false, false),  // 89
+	op("TXA", txa, Implied, 2, true, false),     // 8A
+	op("XAA", xaa, Immediate, 2, false, false),  // 8B
+	op("STY", sty, Absolute, 4, true, false),    // 8C
+	op("STA", sta, Absolute, 4, true, false),    // 8D
+	op("STX", stx, Absolute, 4, true, false),    // 8E
+	op("SAX", sax, Absolute, 4, false, false),   // 8F
+	op("BCC", bcc, Relative, 2, true, false),    // 90
+	op("STA", sta, IndirectY, 6, true, false),   // 91
+	op("JAM", jam, Implied, 0, false, false),    // 92
+	op("SHA", sha, IndirectY, 6, false, false),  // 93
+	op("STY", sty, ZeroPageX, 4, true, false),   // 94
+	op("STA", sta, ZeroPageX, 4, true, false),   // 95
+	op("STX", stx, ZeroPageY, 4, true, false),   // 96
+	op("SAX", sax, ZeroPageY, 4, false, false),  // 97
+	op("TYA", tya, Implied, 2, true, false),     // 98
+	op("STA", sta, AbsoluteY, 5, true, false),   // 99
+	op("TXS", txs, Implied, 2, true, false),     // 9A
+	op("TAS", tas, AbsoluteY, 5, false, false),  // 9B
+	op("SHY", shy, AbsoluteX, 5, false, false),  // 9C
+	op("STA", sta, AbsoluteX, 5, true, false),   // 9D
+	op("SHX", shx, AbsoluteY, 5, false, false),  // 9E
+	op("SHA", sha, AbsoluteY, 5, false, false),  // 9F
+	op("LDY", ldy, Immediate, 2, true, false),   // A0
+	op("LDA", lda, IndirectX, 6, true, false),   // A1
+	op("LDX", ldx, Immediate, 2, true, false),   // A2
+	op("LAX", lax, IndirectX, 6, false, false),  // A3
+	op("LDY", ldy, ZeroPage, 3, true, false),    // A4
+	op("LDA", lda, ZeroPage, 3, true, false),    // A5
+	op("LDX", ldx, ZeroPage, 3, true, false),    // A6
+	op("LAX", lax, ZeroPage, 3, false, false),   // A7
+	op("TAY", tay, Implied, 2, true, false),     // A8
+	op("LDA", lda, Immediate, 2, true, false),   // A9
+	op("TAX", tax, Implied, 2, true, false),     // AA
+	op("LXA", lxa, Immediate, 2, false, false),  // AB
+	op("LDY", ldy, Absolute, 4, true, false),    // AC
+	op("LDA", lda, Absolute, 4, true, false),    // AD
+	op("LDX", ldx, Absolute, 4, true, false),    // AE
+	op("LAX", lax, Absolute, 4, false, false),   // AF
+	op("BCS", bcs, Relative, 2, true, false),    // B0
+	op("LDA", lda, IndirectY, 5, true, true),    // B1
+	op("JAM", jam, Implied, 0, false, false),    // B2
+	op("LAX", lax, IndirectY, 5, false, true),   // B3
+	op("LDY", ldy, ZeroPageX, 4, true, false),   // B4
+	op("LDA", lda, ZeroPageX, 4, true, false),   // B5
+	op("LDX", ldx, ZeroPageY, 4, true, false),   // B6
+	op("LAX", lax, ZeroPageY, 4, false, false),  // B7
+	op("CLV", clv, Implied, 2, true, false),     // B8
+	op("LDA", lda, AbsoluteY, 4, true, true),    // B9
+	op("TSX", tsx, Implied, 2, true, false),     // BA
+	op("LAS", las, AbsoluteY, 4, false, true),   // BB
+	op("LDY", ldy, AbsoluteX, 4, true, true),    // BC
+	op("LDA", lda, AbsoluteX, 4, true, true),    // BD
+	op("LDX", ldx, AbsoluteY, 4, true, true),    // BE
+	op("LAX", lax, AbsoluteY, 4, false, true),   // BF
+	op("CPY", cpy, Immediate, 2, true, false),   // C0
+	op("CMP", cmp, IndirectX, 6, true, false),   // C1
+	op("NOP", nop, Immediate, 2, false, false),  // C2
+	op("DCP", dcp, IndirectX, 8, false, false),  // C3
+	op("CPY", cpy, ZeroPage, 3, true, false),    // C4
+	op("CMP", cmp, ZeroPage, 3, true, false),    // C5
+	op("DEC", dec, ZeroPage, 5, true, false),    // C6
+	op("DCP", dcp, ZeroPage, 5, false, false),   // C7
+	op("INY", iny, Implied, 2, true, false),     // C8
+	op("CMP", cmp, Immediate, 2, true, false),   // C9
+	op("DEX", dex, Implied, 2, true, false),     // CA
+	op("AXS", axs, Immediate, 2, false, false),  // CB
+	op("CPY", cpy, Absolute, 4, true, false),    // CC
+	op("CMP", cmp, Absolute, 4, true, false),    // CD
+	op("DEC", dec, Absolute, 6, true, false),    // CE
+	op("DCP", dcp, Absolute, 6, false, false),   // CF
+	op("BNE", bne, Relative, 2, true, false),    // D0
+	op("CMP", cmp, IndirectY, 5, true, true),    // D1
+	op("JAM", jam, Implied, 0, false, false),    // D2
+	op("DCP", dcp, IndirectY, 8, false, false),  // D3
+	op("NOP", nop, ZeroPageX, 4, false, false),  // D4
+	op("CMP", cmp, ZeroPageX, 4, true, false),   // D5
+	op("DEC", dec, ZeroPageX, 6, true, false),   // D6
+	op("DCP", dcp, ZeroPageX, 6, false, false),  // D7
+	op("CLD", cld, Implied, 2, true, false),     // D8
+	op("CMP", cmp, AbsoluteY, 4, true, true),    // D9
+	op("NOP", nop, Implied, 2, false, false),    // DA
+	op("DCP", dcp, AbsoluteY, 7, false, false),  // DB
+	op("NOP", nop, AbsoluteX, 4, false, true),   // DC
+	op("CMP", cmp, AbsoluteX, 4, true, true),    // DD
+	op("DEC", dec, AbsoluteX, 7, true, false),   // DE
+	op("DCP", dcp, AbsoluteX, 7, false, false),  // DF
+	op("CPX", cpx, Immediate, 2, true, false),   // E0
+	op("SBC", sbc, IndirectX, 6, true, false),   // E1
+	op("NOP", nop, Immediate, 2, false, false),  // E2
+	op("ISC", isc, IndirectX, 8, false, false),  // E3
+	op("CPX", cpx, ZeroPage, 3, true, false),    // E4
+	op("SBC", sbc, ZeroPage, 3, true, false),    // E5
+	op("INC", inc, ZeroPage, 5, true, false),    // E6
+	op("ISC", isc, ZeroPage, 5, false, false),   // E7
+	op("INX", inx, Implied, 2, true, false),     // E8
+	op("SBC", sbc, Immediate, 2, true, false),   // E9
+	op("NOP", nop, Implied, 2, true, false),     // EA
+	op("SBC", sbc, Immediate, 2, false, false),  // EB
+	op("CPX", cpx, Absolute, 4, true, false),    // EC
+	op("SBC", sbc, Absolute, 4, true, false),    // ED
+	op("INC", inc, Absolute, 6, true, false),    // EE
+	op("ISC", isc, Absolute, 6, false, false),   // EF
+	op("BEQ", beq, Relative, 2, true, false),    // F0
+	op("SBC", sbc, IndirectY, 5, true, true),    // F1
+	op("JAM", jam, Implied, 0, false, false),    // F2
+	op("ISC", isc, IndirectY, 8, false, false),  // F3
+	op("NOP", nop, ZeroPageX, 4, false, false),  // F4
+	op("SBC", sbc, ZeroPageX, 4, true, false),   // F5
+	op("INC", inc, ZeroPageX, 6, true, false),   // F6
+	op("ISC", isc, ZeroPageX, 6, false, false),  // F7
+	op("SED", sed, Implied, 2, true, false),     // F8
+	op("SBC", sbc, AbsoluteY, 4, true, true),    // F9
+	op("NOP", nop, Implied, 2, false, false),    // FA
+	op("ISC", isc, AbsoluteY, 7, false, false),  // FB
+	op("NOP", nop, AbsoluteX, 4, false, true),   // FC
+	op("SBC", sbc, AbsoluteX, 4, true, true),    // FD
+	op("INC", inc, AbsoluteX, 7, true, false),   // FE
+	op("ISC", isc, AbsoluteX, 7, false, false),  // FF
 ];
@@ -0,0 +1,461 @@
+//
+// 65C02 (WDC W65C02S, with the Rockwell bit instructions)
+//	http://www.6502.org/tutorials/65c02opcodes.html
+//	https://www.westerndesigncenter.com/wdc/documentation/w65c02s.pdf
+//
+// Shares the NMOS instruction functions where behaviour is the same; the
+// differences in existing instructions (BIT #, INC A/DEC A, decimal flags,
+// D cleared on interrupts) check the variant or addressing mode there.
+// Every undefined opcode is a NOP of fixed length and timing.
+
+
+use super::*;
+
+
+//
+// New Instructions
+
+// Always taken, so the taken cycle is already in its 3
+fn bra(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	branch(cpu, true) - 1
+}
+fn phx(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.push(bus, cpu.x);
+
+	0
+}
+fn phy(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.push(bus, cpu.y);
+
+	0
+}
+fn plx(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.x = cpu.pull(bus);
+	cpu.sr.set_zn(cpu.x);
+
+	0
+}
+fn ply(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.y = cpu.pull(bus);
+	cpu.sr.set_zn(cpu.y);
+
+	0
+}
+fn stp(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.halt(cpu.pc.wrapping_sub(1));
+
+	0
+}
+fn stz(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	bus.write(cpu.addr_abs, 0x00);
+
+	0
+}
+fn trb(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	cpu.sr.set(Status::Z, cpu.ac & cpu.fetched == 0);
+	bus.write(cpu.addr_abs, cpu.fetched & !cpu.ac);
+
+	0
+}
+fn tsb(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	cpu.fetch(bus);
+
+	cpu.sr.set(Status::Z, cpu.ac & cpu.fetched == 0);
+	bus.write(cpu.addr_abs, cpu.fetched | cpu.ac);
+
+	0
+}
+fn wai(cpu: &mut Cpu, _: &mut dyn Bus) -> u8 {
+	cpu.state = CpuState::Waiting;
+
+	0
+}
+
+// RMBn / SMBn: clear or set bit n of a zero page byte
+fn rmb0(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 0, false) }
+fn rmb1(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 1, false) }
+fn rmb2(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 2, false) }
+fn rmb3(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 3, false) }
+fn rmb4(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 4, false) }
+fn rmb5(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 5, false) }
+fn rmb6(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 6, false) }
+fn rmb7(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 7, false) }
+fn smb0(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 0, true) }
+fn smb1(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 1, true) }
+fn smb2(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 2, true) }
+fn smb3(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 3, true) }
+fn smb4(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 4, true) }
+fn smb5(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 5, true) }
+fn smb6(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 6, true) }
+fn smb7(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { change_bit(cpu, bus, 7, true) }
+
+// BBRn / BBSn: branch if bit n of a zero page byte is clear / set
+fn bbr0(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 0, false) }
+fn bbr1(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 1, false) }
+fn bbr2(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 2, false) }
+fn bbr3(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 3, false) }
+fn bbr4(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 4, false) }
+fn bbr5(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 5, false) }
+fn bbr6(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 6, false) }
+fn bbr7(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 7, false) }
+fn bbs0(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 0, true) }
+fn bbs1(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 1, true) }
+fn bbs2(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 2, true) }
+fn bbs3(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 3, true) }
+fn bbs4(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 4, true) }
+fn bbs5(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 5, true) }
+fn bbs6(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 6, true) }
+fn bbs7(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 { branch_on_bit(cpu, bus, 7, true) }
+
+
+//
+// Helpers
+
+fn change_bit(cpu: &mut Cpu, bus: &mut dyn Bus, bit: u8, set: bool) -> u8 {
+	cpu.fetch(bus);
+
+	let tmp = if set { cpu.fetched | (1 << bit) } else { cpu.fetched & !(1 << bit) };
+	bus.write(cpu.addr_abs, tmp);
+
+	0
+}
+
+fn branch_on_bit(cpu: &mut Cpu, bus: &mut dyn Bus, bit: u8, set: bool) -> u8 {
+	cpu.fetch(bus);
+
+	branch(cpu, (cpu.fetched & (1 << bit) != 0) == set)
+}
+
+// 65C02 BCD: same result, carry and overflow as the NMOS chip, but N and Z
+// are valid for the decimal result. Costs one extra cycle, counted by the
+// caller.
+pub(super) fn add_decimal_cmos(cpu: &mut Cpu, value: u8) {
+	add_decimal(cpu, value);
+	cpu.sr.set_zn(cpu.ac);
+}
+
+//	http://www.6502.org/tutorials/decimal_mode.html#A
+pub(super) fn subtract_decimal_cmos(cpu: &mut Cpu, value: u8) {
+	let (a, m) = (cpu.ac as i16, value as i16);
+	let borrow = 1 - cpu.sr.carry() as i16;
+
+	let lo = (a & 0x0F) - (m & 0x0F) - borrow;
+	let mut tmp = a - m - borrow;
+	if tmp < 0 {
+		tmp -= 0x60;
+	}
+	if lo < 0 {
+		tmp -= 0x06;
+	}
+
+	add(cpu, value ^ 0xFF);
+	cpu.ac = tmp as u8;
+	cpu.sr.set_zn(cpu.ac);
+}
+
+
+//
+// Address Modes
+
+// JMP ($xxFF) reads its high byte from the next page, unlike the NMOS bug
+pub(super) fn ind(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let lo = bus.read(cpu.pc) as u16;
+	let hi = bus.read(cpu.pc.wrapping_add(1)) as u16;
+	cpu.pc = cpu.pc.wrapping_add(2);
+
+	let ptr = (hi << 8) | lo;
+	cpu.addr_abs = bus.read(ptr) as u16 | ((bus.read(ptr.wrapping_add(1)) as u16) << 8);
+
+	0
+}
+
+// (zp): zero page pointer without indexing
+pub(super) fn izp(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let ptr = bus.read(cpu.pc);
+	cpu.pc = cpu.pc.wrapping_add(1);
+
+	cpu.addr_abs = bus.read(ptr as u16) as u16 | ((bus.read(ptr.wrapping_add(1) as u16) as u16) << 8);
+
+	0
+}
+
+// (abs,X): JMP through a table of addresses
+pub(super) fn iax(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	let lo = bus.read(cpu.pc) as u16;
+	let hi = bus.read(cpu.pc.wrapping_add(1)) as u16;
+	cpu.pc = cpu.pc.wrapping_add(2);
+
+	let ptr = ((hi << 8) | lo).wrapping_add(cpu.x as u16);
+	cpu.addr_abs = bus.read(ptr) as u16 | ((bus.read(ptr.wrapping_add(1)) as u16) << 8);
+
+	0
+}
+
+// zp, rel: BBR/BBS test a zero page byte, then branch relative
+pub(super) fn zpr(cpu: &mut Cpu, bus: &mut dyn Bus) -> u8 {
+	zpg(cpu, bus);
+	rel(cpu, bus)
+}
+
+
+pub const LOOK_UP_65C02: [Opcodes; 256] = [
+	// mnemonic, instruction, addressing mode, cycles, official, page penalty
+	op("BRK", brk, Implied, 7, true, false),            // 00
+	op("ORA", ora, IndirectX, 6, true, false),          // 01
+	op("NOP", nop, Immediate, 2, false, false),         // 02
+	op("NOP", nop, Implied, 1, false, false),           // 03
+	op("TSB", tsb, ZeroPage, 5, true, false),           // 04
+	op("ORA", ora, ZeroPage, 3, true, false),           // 05
+	op("ASL", asl, ZeroPage, 5, true, false),           // 06
+	op("RMB0", rmb0, ZeroPage, 5, true, false),         // 07
+	op("PHP", php, Implied, 3, true, false),            // 08
+	op("ORA", ora, Immediate, 2, true, false),          // 09
+	op("ASL", asl, Accumulator, 2, true, false),        // 0A
+	op("NOP", nop, Implied, 1, false, false),           // 0B
+	op("TSB", tsb, Absolute, 6, true, false),           // 0C
+	op("ORA", ora, Absolute, 4, true, false),           // 0D
+	op("ASL", asl, Absolute, 6, true, false),           // 0E
+	op("BBR0", bbr0, ZeroPageRelative, 5, true, false), // 0F
+	op("BPL", bpl, Relative, 2, true, false),           // 10
+	op("ORA", ora, IndirectY, 5, true, true),           // 11
+	op("ORA", ora, ZeroPageIndirect, 5, true, false),   // 12
+	op("NOP", nop, Implied, 1, false, false),           // 13
+	op("TRB", trb, ZeroPage, 5, true, false),           // 14
+	op("ORA", ora, ZeroPageX, 4, true, false),          // 15
+	op("ASL", asl, ZeroPageX, 6, true, false),          // 16
+	op("RMB1", rmb1, ZeroPage, 5, true, false),         // 17
+	op("CLC", clc, Implied, 2, true, false),            // 18
+	op("ORA", ora, AbsoluteY, 4, true, true),           // 19
+	op("INC", inc, Accumulator, 2, true, false),        // 1A
+	op("NOP", nop, Implied, 1, false, false),           // 1B
+	op("TRB", trb, Absolute, 6, true, false),           // 1C
+	op("ORA", ora, AbsoluteX, 4, true, true),           // 1D
+	op("ASL", asl, AbsoluteX, 6, true, true),           // 1E
+	op("BBR1", bbr1, ZeroPageRelative, 5, true, false), // 1F
+	op("JSR", jsr, Absolute, 6, true, false),           // 20
+	op("AND", and, IndirectX, 6, true, false),          // 21
+	op("NOP", nop, Immediate, 2, false, false),         // 22
+	op("NOP", nop, Implied, 1, false, false),           // 23
+	op("BIT", bit, ZeroPage, 3, true, false),           // 24
+	op("AND", and, ZeroPage, 3, true, false),           // 25
+	op("ROL", rol, ZeroPage, 5, true, false),           // 26
+	op("RMB2", rmb2, ZeroPage, 5, true, false),         // 27
+	op("PLP", plp, Implied, 4, true, false),            // 28
+	op("AND", and, Immediate, 2, true, false),          // 29
+	op("ROL", rol, Accumulator, 2, true, false),        // 2A
+	op("NOP", nop, Implied, 1, false, false),           // 2B
+	op("BIT", bit, Absolute, 4, true, false),           // 2C
+	op("AND", and, Absolute, 4, true, false),           // 2D
+	op("ROL", rol, Absolute, 6, true, false),           // 2E
+	op("BBR2", bbr2, ZeroPageRelative, 5, true, false), // 2F
+	op("BMI", bmi, Relative, 2, true, false),           // 30
+	op("AND", and, IndirectY, 5, true, true),           // 31
+	op("AND", and, ZeroPageIndirect, 5, true, false),   // 32
+	op("NOP", nop, Implied, 1, false, false),           // 33
+	op("BIT", bit, ZeroPageX, 4, true, false),          // 34
+	op("AND", and, ZeroPageX, 4, true, false),          // 35
+	op("ROL", rol, ZeroPageX, 6, true, false),          // 36
+	op("RMB3", rmb3, ZeroPage, 5, true, false),         // 37
+	op("SEC", sec, Implied, 2, true, false),            // 38
+	op("AND", and, AbsoluteY, 4, true, true),           // 39
+	op("DEC", dec, Accumulator, 2, true, false),        // 3A
+	op("NOP", nop, Implied, 1, false, false),           // 3B
+	op("BIT", bit, AbsoluteX, 4, true, true),           // 3C
+	op("AND", and, AbsoluteX, 4, true, true),           // 3D
+	op("ROL", rol, AbsoluteX, 6, true, true),           // 3E
+	op("BBR3", bbr3, ZeroPageRelative, 5, true, false), // 3F
+	op("RTI", rti, Implied, 6, true, false),            // 40
+	op("EOR", eor, IndirectX, 6, true, false),          // 41
+	op("NOP", nop, Immediate, 2, false, false),         // 42
+	op("NOP", nop, Implied, 1, false, false),           // 43
+	op("NOP", nop, ZeroPage, 3, false, false),          // 44
+	op("EOR", eor, ZeroPage, 3, true, false),           // 45
+	op("LSR", lsr, ZeroPage, 5, true, false),           // 46
+	op("RMB4", rmb4, ZeroPage, 5, true, false),         // 47
+	op("PHA", pha, Implied, 3, true, false),            // 48
+	op("EOR", eor, Immediate, 2, true, false),          // 49
+	op("LSR", lsr, Accumulator, 2, true, false),        // 4A
+	op("NOP", nop, Implied, 1, false, false),           // 4B
+	op("JMP", jmp, Absolute, 3, true, false),           // 4C
+	op("EOR", eor, Absolute, 4, true, false),           // 4D
+	op("LSR", lsr, Absolute, 6, true, false),           // 4E
+	op("BBR4", bbr4, ZeroPageRelative, 5, true, false), // 4F
+	op("BVC", bvc, Relative, 2, true, false),           // 50
+	op("EOR", eor, IndirectY, 5, true, true),           // 51
+	op("EOR", eor, ZeroPageIndirect, 5, true, false),   // 52
+	op("NOP", nop, Implied, 1, false, false),           // 53
+	op("NOP", nop, ZeroPageX, 4, false, false),         // 54
+	op("EOR", eor, ZeroPageX, 4, true, false),          // 55
+	op("LSR", lsr, ZeroPageX, 6, true, false),          // 56
+	op("RMB5", rmb5, ZeroPage, 5, true, false),         // 57
+	op("CLI", cli, Implied, 2, true, false),            // 58
+	op("EOR", eor, AbsoluteY, 4, true, true),           // 59
+	op("PHY", phy, Implied, 3, true, false),            // 5A
+	op("NOP", nop, Implied, 1, false, false),           // 5B
+	op("NOP", nop, Absolute, 8, false, false),          // 5C
+	op("EOR", eor, AbsoluteX, 4, true, true),           // 5D
+	op("LSR", lsr, AbsoluteX, 6, true, true),           // 5E
+	op("BBR5", bbr5, ZeroPageRelative, 5, true, false), // 5F
+	op("RTS", rts, Implied, 6, true, false),            // 60
+	op("ADC", adc, IndirectX, 6, true, false),          // 61
+	op("NOP", nop, Immediate, 2, false, false),         // 62
+	op("NOP", nop, Implied, 1, false, false),           // 63
+	op("STZ", stz, ZeroPage, 3, true, false),           // 64
+	op("ADC", adc, ZeroPage, 3, true, false),           // 65
+	op("ROR", ror, ZeroPage, 5, true, false),           // 66
+	op("RMB6", rmb6, ZeroPage, 5, true, false),         // 67
+	op("PLA", pla, Implied, 4, true, false),            // 68
+	op("ADC", adc, Immediate, 2, true, false),          // 69
+	op("ROR", ror, Accumulator, 2, true, false),        // 6A
+	op("NOP", nop, Implied, 1, false, false),           // 6B
+	op("JMP", jmp, Indirect, 6, true, false),           // 6C
+	op("ADC", adc, Absolute, 4, true, false),           // 6D
+	op("ROR", ror, Absolute, 6, true, false),           // 6E
+	op("BBR6", bbr6, ZeroPageRelative, 5, true, false), // 6F
+	op("BVS", bvs, Relative, 2, true, false),           // 70
+	op("ADC", adc, IndirectY, 5, true, true),           // 71
+	op("ADC", adc, ZeroPageIndirect, 5, true, false),   // 72
+	op("NOP", nop, Implied, 1, false, false),           // 73
+	op("STZ", stz, ZeroPageX, 4, true, false),          // 74
+	op("ADC", adc, ZeroPageX, 4, true, false),          // 75
+	op("ROR", ror, ZeroPageX, 6, true, false),          // 76
+	op("RMB7", rmb7, ZeroPage, 5, true, false),         // 77
+	op("SEI", sei, Implied, 2, true, false),            // 78
+	op("ADC", adc, AbsoluteY, 4, true, true),           // 79
+	op("PLY", ply, Implied, 4, true, false),            // 7A
+	op("NOP", nop, Implied, 1, false, false),           // 7B
+	op("JMP", jmp, AbsoluteIndirectX, 6, true, false),  // 7C
+	op("ADC", adc, AbsoluteX, 4, true, true),           // 7D
+	op("ROR", ror, AbsoluteX, 6, true, true),           // 7E
+	op("BBR7", bbr7, ZeroPageRelative, 5, true, false), // 7F
+	op("BRA", bra, Relative, 3, true, false),           // 80
+	op("STA", sta, IndirectX, 6, true, false),          // 81
+	op("NOP", nop, Immediate, 2, false, false),         // 82
+	op("NOP", nop, Implied, 1, false, false),           // 83
+	op("STY", sty, ZeroPage, 3, true, false),           // 84
+	op("STA", sta, ZeroPage, 3, true, false),           // 85
+	op("STX", stx, ZeroPage, 3, true, false),           // 86
+	op("SMB0", smb0, ZeroPage, 5, true, false),         // 87
+	op("DEY", dey, Implied, 2, true, false),            // 88
+	op("BIT", bit, Immediate, 2, true, false),          // 89
+	op("TXA", txa, Implied, 2, true, false),            // 8A
+	op("NOP", nop, Implied, 1, false, false),           // 8B
+	op("STY", sty, Absolute, 4, true, false),           // 8C
+	op("STA", sta, Absolute, 4, true, false),           // 8D
+	op("STX", stx, Absolute, 4, true, false),           // 8E
+	op("BBS0", bbs0, ZeroPageRelative, 5, true, false), // 8F
+	op("BCC", bcc, Relative, 2, true, false),           // 90
+	op("STA", sta, IndirectY, 6, true, false),          // 91
+	op("STA", sta, ZeroPageIndirect, 5, true, false),   // 92
+	op("NOP", nop, Implied, 1, false, false),           // 93
+	op("STY", sty, ZeroPageX, 4, true, false),          // 94
+	op("STA", sta, ZeroPageX, 4, true, false),          // 95
+	op("STX", stx, ZeroPageY, 4, true, false),          // 96
+	op("SMB1", smb1, ZeroPage, 5, true, false),         // 97
+	op("TYA", tya, Implied, 2, true, false),            // 98
+	op("STA", sta, AbsoluteY, 5, true, false),          // 99
+	op("TXS", txs, Implied, 2, true, false),            // 9A
+	op("NOP", nop, Implied, 1, false, false),           // 9B
+	op("STZ", stz, Absolute, 4, true, false),           // 9C
+	op("STA", sta, AbsoluteX, 5, true, false),          // 9D
+	op("STZ", stz, AbsoluteX, 5, true, false),          // 9E
+	op("BBS1", bbs1, ZeroPageRelative, 5, true, false), // 9F
+	op("LDY", ldy, Immediate, 2, true, false),          // A0
+	op("LDA", lda, IndirectX, 6, true, false),          // A1
+	op("LDX", ldx, Immediate, 2, true, false),          // A2
+	op("NOP", nop, Implied, 1, false, false),           // A3
+	op("LDY", ldy, ZeroPage, 3, true, false),           // A4
+	op("LDA", lda, ZeroPage, 3, true, false),           // A5
+	op("LDX", ldx, ZeroPage, 3, true, false),           // A6
+	op("SMB2", smb2, ZeroPage, 5, true, false),         // A7
+	op("TAY", tay, Implied, 2, true, false),            // A8
+	op("LDA", lda, Immediate, 2, true, false),          // A9
+	op("TAX", tax, Implied, 2, true, false),            // AA
+	op("NOP", nop, Implied, 1, false, false),           // AB
+	op("LDY", ldy, Absolute, 4, true, false),           // AC
+	op("LDA", lda, Absolute, 4, true, false),           // AD
+	op("LDX", ldx, Absolute, 4, true, false),           // AE
+	op("BBS2", bbs2, ZeroPageRelative, 5, true, false), // AF
+	op("BCS", bcs, Relative, 2, true, false),           // B0
+	op("LDA", lda, IndirectY, 5, true, true),           // B1
+	op("LDA", lda, ZeroPageIndirect, 5, true, false),   // B2
+	op("NOP", nop, Implied, 1, false, false),           // B3
+	op("LDY", ldy, ZeroPageX, 4, true, false),          // B4
+	op("LDA", lda, ZeroPageX, 4, true, false),          // B5
+	op("LDX", ldx, ZeroPageY, 4, true, false),          // B6
+	op("SMB3", smb3, ZeroPage, 5, true, false),         // B7
+	op("CLV", clv, Implied, 2, true, false),            // B8
+	op("LDA", lda, AbsoluteY, 4, true, true),           // B9
+	op("TSX", tsx, Implied, 2, true, false),            // BA
+	op("NOP", nop, Implied, 1, false, false),           // BB
+	op("LDY", ldy, AbsoluteX, 4, true, true),           // BC
+	op("LDA", lda, AbsoluteX, 4, true, true),           // BD
+	op("LDX", ldx, AbsoluteY, 4, true, true),           // BE
+	op("BBS3", bbs3, ZeroPageRelative, 5, true, false), // BF
+	op("CPY", cpy, Immediate, 2, true, false),          // C0
+	op("CMP", cmp, IndirectX, 6, true, false),          // C1
+	op("NOP", nop, Immediate, 2, false, false),         // C2
+	op("NOP", nop, Implied, 1, false, false),           // C3
+	op("CPY", cpy, ZeroPage, 3, true, false),           // C4
+	op("CMP", cmp, ZeroPage, 3, true, false),           // C5
+	op("DEC", dec, ZeroPage, 5, true, false),           // C6
+	op("SMB4", smb4, ZeroPage, 5, true, false),         // C7
+	op("INY", iny, Implied, 2, true, false),            // C8
+	op("CMP", cmp, Immediate, 2, true, false),          // C9
+	op("DEX", dex, Implied, 2, true, false),            // CA
+	op("WAI", wai, Implied, 3, true, false),            // CB
+	op("CPY", cpy, Absolute, 4, true, false),           // CC
+	op("CMP", cmp, Absolute, 4, true, false),           // CD
+	op("DEC", dec, Absolute, 6, true, false),           // CE
+	op("BBS4", bbs4, ZeroPageRelative, 5, true, false), // CF
+	op("BNE", bne, Relative, 2, true, false),           // D0
+	op("CMP", cmp, IndirectY, 5, true, true),           // D1
+	op("CMP", cmp, ZeroPageIndirect, 5, true, false),   // D2
+	op("NOP", nop, Implied, 1, false, false),           // D3
+	op("NOP", nop, ZeroPageX, 4, false, false),         // D4
+	op("CMP", cmp, ZeroPageX, 4, true, false),          // D5
+	op("DEC", dec, ZeroPageX, 6, true, false),          // D6
+	op("SMB5", smb5, ZeroPage, 5, true, false),         // D7
+	op("CLD", cld, Implied, 2, true, false),            // D8
+	op("CMP", cmp, AbsoluteY, 4, true, true),           // D9
+	op("PHX", phx, Implied, 3, true, false),            // DA
+	op("STP", stp, Implied, 3, true, false),            // DB
+	op("NOP", nop, Absolute, 4, false, false),          // DC
+	op("CMP", cmp, AbsoluteX, 4, true, true),           // DD
+	op("DEC", dec, AbsoluteX, 7, true, false),          // DE
+	op("BBS5", bbs5, ZeroPageRelative, 5, true, false), // DF
+	op("CPX", cpx, Immediate, 2, true, false),          // E0
+	op("SBC", sbc, IndirectX, 6, true, false),          // E1
+	op("NOP", nop, Immediate, 2, false, false),         // E2
+	op("NOP", nop, Implied, 1, false, false),           // E3
+	op("CPX", cpx, ZeroPage, 3, true, false),           // E4
+	op("SBC", sbc, ZeroPage, 3, true, false),           // E5
+	op("INC", inc, ZeroPage, 5, true, false),           // E6
+	op("SMB6", smb6, ZeroPage, 5, true, false),         // E7
+	op("INX", inx, Implied, 2, true, false),            // E8
+	op("SBC", sbc, Immediate, 2, true, false),          // E9
+	op("NOP", nop, Implied, 2, true, false),            // EA
+	op("NOP", nop, Implied, 1, false, false),           // EB
+	op("CPX", cpx, Absolute, 4, true, false),           // EC
+	op("SBC", sbc, Absolute, 4, true, false),           // ED
+	op("INC", inc, Absolute, 6, true, false),           // EE
+	op("BBS6", bbs6, ZeroPageRelative, 5, true, false), // EF
+	op("BEQ", beq, Relative, 2, true, false),           // F0
+	op("SBC", sbc, IndirectY, 5, true, true),           // F1
+	op("SBC", sbc, ZeroPageIndirect, 5, true, false),   // F2
+	op("NOP", nop, Implied, 1, false, false),           // F3
+	op("NOP", nop, ZeroPageX, 4, false, false),         // F4
+	op("SBC", sbc, ZeroPageX, 4, true, false),          // F5
+	op("INC", inc, ZeroPageX, 6, true, false),          // F6
+	op("SMB7", smb7, ZeroPage, 5, true, false),         // F7
+	op("SED", sed, Implied, 2, true, false),            // F8
+	op("SBC", sbc, AbsoluteY, 4, true, true),           // F9
+	op("PLX", plx, Implied, 4, true, false),            // FA
+	op("NOP", nop, Implied, 1, false, false),           // FB
+	op("NOP", nop, Absolute, 4, false, false),          // FC
+	op("SBC", sbc, AbsoluteX, 4, true, true),           // FD
+	op("INC", inc, AbsoluteX, 7, true, false),          // FE
+	op("BBS7", bbs7, ZeroPageRelative, 5, true, false), // FF
+];
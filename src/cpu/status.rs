@@ -0,0 +1,107 @@
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Not};
+
+// Processor status register: N V 1 B D I Z C
+//
+// Bit 5 (U) and B have no storage in the chip. They only show up in the
+// byte pushed by BRK/PHP/interrupts and are dropped again on PLP/RTI.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Status(u8);
+
+impl Status {
+	pub const C: Status = Status(0b00000001);	// Carry
+	pub const Z: Status = Status(0b00000010);	// Zero
+	pub const I: Status = Status(0b00000100);	// Interrupt disable
+	pub const D: Status = Status(0b00001000);	// Decimal
+	pub const B: Status = Status(0b00010000);	// Break
+	pub const U: Status = Status(0b00100000);	// Unused, reads as 1
+	pub const V: Status = Status(0b01000000);	// Overflow
+	pub const N: Status = Status(0b10000000);	// Negative
+
+	pub const fn from_bits(bits: u8) -> Self {
+		Status(bits)
+	}
+
+	pub const fn bits(self) -> u8 {
+		self.0
+	}
+
+	pub const fn contains(self, flags: Status) -> bool {
+		self.0 & flags.0 == flags.0
+	}
+
+	pub fn insert(&mut self, flags: Status) {
+		self.0 |= flags.0;
+	}
+
+	pub fn remove(&mut self, flags: Status) {
+		self.0 &= !flags.0;
+	}
+
+	pub fn set(&mut self, flags: Status, cond: bool) {
+		if cond {
+			self.insert(flags);
+		} else {
+			self.remove(flags);
+		}
+	}
+
+	// Z and N as almost every instruction leaves them for its result
+	pub fn set_zn(&mut self, value: u8) {
+		self.set(Status::Z, value == 0x00);
+		self.set(Status::N, (value & 0x80) != 0);
+	}
+
+	// Carry as 0 or 1, for arithmetic
+	pub const fn carry(self) -> u8 {
+		self.0 & Status::C.0
+	}
+}
+
+impl BitOr for Status {
+	type Output = Status;
+
+	fn bitor(self, rhs: Status) -> Status {
+		Status(self.0 | rhs.0)
+	}
+}
+
+impl BitAnd for Status {
+	type Output = Status;
+
+	fn bitand(self, rhs: Status) -> Status {
+		Status(self.0 & rhs.0)
+	}
+}
+
+impl Not for Status {
+	type Output = Status;
+
+	fn not(self) -> Status {
+		Status(!self.0)
+	}
+}
+
+impl From<u8> for Status {
+	fn from(bits: u8) -> Self {
+		Status(bits)
+	}
+}
+
+impl From<Status> for u8 {
+	fn from(status: Status) -> u8 {
+		status.0
+	}
+}
+
+// Set flags upper case, clear ones lower case: "Nv-bdIzC"
+impl fmt::Debug for Status {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let names = b"czidb-vn";
+		let text: String = names.iter().enumerate().rev()
+			.map(|(bit, &c)| if self.0 & (1 << bit) != 0 { c.to_ascii_uppercase() as char } else { c as char })
+			.collect();
+
+		write!(f, "{}", text)
+	}
+}
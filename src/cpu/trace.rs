@@ -0,0 +1,117 @@
+// Execution trace in the format of nestest.log:
+//	https://www.qmtpro.com/~nes/misc/nestest.log
+//
+//	C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+//
+// One line per instruction, written before it executes. Memory shown in
+// the operand annotations is read with peek, so tracing never disturbs
+// registers with read side effects.
+
+
+use super::*;
+use crate::disasm;
+
+impl Cpu {
+
+	// Sends every following instruction's trace line to out
+	pub fn trace_to(&mut self, mut out: impl std::io::Write + 'static) {
+		self.trace = Some(Box::new(move |line: &str| {
+			let _ = writeln!(out, "{}", line);
+		}));
+	}
+
+	// Called on an opcode fetch cycle, before the opcode is read
+	pub(super) fn emit_trace(&mut self, bus: &dyn Bus) {
+		if let Some(mut trace) = self.trace.take() {
+			trace(&self.trace_line(bus));
+			self.trace = Some(trace);
+		}
+	}
+
+	// Trace line for the instruction at pc
+	pub fn trace_line(&self, bus: &dyn Bus) -> String {
+		let instruction = disasm::decode_for(self.variant, |addr| bus.peek(addr), self.pc);
+		let raw: Vec<String> = instruction.raw().iter().map(|b| format!("{:02X}", b)).collect();
+
+		// nestest knows ISC as ISB
+		let mnemonic = match instruction.mnemonic {
+			"ISC" => "ISB",
+			name => name,
+		};
+		let operand = self.trace_operand(bus, &instruction);
+		let text = if operand.is_empty() { mnemonic.to_string() } else { format!("{} {}", mnemonic, operand) };
+
+		// Cycles completed before this one, the fetch
+		let cyc = self.global_clock.saturating_sub(1);
+		let (scanline, dot) = bus.ppu_position().unwrap_or_else(|| {
+			let dots = cyc * 3;
+			(((dots / 341) % 262) as u16, (dots % 341) as u16)
+		});
+
+		format!(
+			"{:04X}  {:<8} {}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+			self.pc, raw.join(" "), if instruction.official { ' ' } else { '*' }, text,
+			self.ac, self.x, self.y, self.sr.bits(), self.sp, scanline, dot, cyc,
+		)
+	}
+
+	// Operand with the effective address and the value found there
+	fn trace_operand(&self, bus: &dyn Bus, instruction: &disasm::Instruction) -> String {
+		let op = instruction.operand;
+		let zp_word = |ptr: u8| bus.peek(ptr as u16) as u16 | ((bus.peek(ptr.wrapping_add(1) as u16) as u16) << 8);
+
+		match instruction.mode {
+			AddressingMode::Implied => String::new(),
+			AddressingMode::Accumulator => "A".to_string(),
+			AddressingMode::Immediate => format!("#${:02X}", op),
+			AddressingMode::Relative => format!("${:04X}", instruction.target.unwrap_or(op)),
+			AddressingMode::ZeroPage => format!("${:02X} = {:02X}", op, bus.peek(op)),
+			AddressingMode::ZeroPageX | AddressingMode::ZeroPageY => {
+				let (index, name) = if instruction.mode == AddressingMode::ZeroPageX { (self.x, 'X') } else { (self.y, 'Y') };
+				let addr = (op as u8).wrapping_add(index) as u16;
+				format!("${:02X},{} @ {:02X} = {:02X}", op, name, addr, bus.peek(addr))
+			}
+			AddressingMode::Absolute => match instruction.mnemonic {
+				"JMP" | "JSR" => format!("${:04X}", op),
+				_ => format!("${:04X} = {:02X}", op, bus.peek(op)),
+			},
+			AddressingMode::AbsoluteX | AddressingMode::AbsoluteY => {
+				let (index, name) = if instruction.mode == AddressingMode::AbsoluteX { (self.x, 'X') } else { (self.y, 'Y') };
+				let addr = op.wrapping_add(index as u16);
+				format!("${:04X},{} @ {:04X} = {:02X}", op, name, addr, bus.peek(addr))
+			}
+			AddressingMode::Indirect => {
+				// On NMOS chips the pointer's high byte never leaves its page
+				let hi_addr = match self.variant {
+					Variant::Wdc65C02 => op.wrapping_add(1),
+					_ => (op & 0xFF00) | (op as u8).wrapping_add(1) as u16,
+				};
+				let target = bus.peek(op) as u16 | ((bus.peek(hi_addr) as u16) << 8);
+				format!("(${:04X}) = {:04X}", op, target)
+			}
+			AddressingMode::IndirectX => {
+				let ptr = (op as u8).wrapping_add(self.x);
+				let addr = zp_word(ptr);
+				format!("(${:02X},X) @ {:02X} = {:04X} = {:02X}", op, ptr, addr, bus.peek(addr))
+			}
+			AddressingMode::IndirectY => {
+				let base = zp_word(op as u8);
+				let addr = base.wrapping_add(self.y as u16);
+				format!("(${:02X}),Y = {:04X} @ {:04X} = {:02X}", op, base, addr, bus.peek(addr))
+			}
+			AddressingMode::ZeroPageIndirect => {
+				let addr = zp_word(op as u8);
+				format!("(${:02X}) = {:04X} = {:02X}", op, addr, bus.peek(addr))
+			}
+			AddressingMode::AbsoluteIndirectX => {
+				let ptr = op.wrapping_add(self.x as u16);
+				let target = bus.peek(ptr) as u16 | ((bus.peek(ptr.wrapping_add(1)) as u16) << 8);
+				format!("(${:04X},X) = {:04X}", op, target)
+			}
+			AddressingMode::ZeroPageRelative => {
+				format!("${:02X} = {:02X},${:04X}", op, bus.peek(op), instruction.target.unwrap_or(0))
+			}
+		}
+	}
+
+}
@@ -0,0 +1,170 @@
+//
+// 6502 Disassembler
+//
+// Decodes straight from the opcode tables, so it knows every opcode the
+// CPU does.
+// Memory is read through a plain peek function, which lets the same code
+// disassemble a Bus (|a| bus.peek(a)), a ROM slice or anything else
+// without side effects on the hardware.
+
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::cpu::{AddressingMode, Variant};
+
+pub type Labels = HashMap<u16, String>;
+
+pub struct Instruction {
+	pub addr: u16,
+	pub opcode: u8,
+	pub bytes: [u8; 3],
+	pub len: u8,
+	pub mnemonic: &'static str,
+	pub mode: AddressingMode,
+	pub official: bool,
+	pub operand: u16,			// Raw operand, zero page or absolute
+	pub target: Option<u16>,	// Resolved destination of relative branches (and BBR/BBS)
+}
+
+impl Instruction {
+
+	// Raw bytes of the instruction, opcode first
+	pub fn raw(&self) -> &[u8] {
+		&self.bytes[..self.len as usize]
+	}
+
+	// Address of the following instruction
+	pub fn next(&self) -> u16 {
+		self.addr.wrapping_add(self.len as u16)
+	}
+
+	// Operand in standard syntax: "#$20", "($20),Y", "$C012", "A"...
+	// Addresses found in labels are printed by name instead.
+	pub fn operand_text(&self, labels: Option<&Labels>) -> String {
+		let name = |addr: u16, hex: String| -> String {
+			labels.and_then(|l| l.get(&addr)).cloned().unwrap_or(hex)
+		};
+		let zp = |addr: u16| name(addr, format!("${:02X}", addr));
+		let abs = |addr: u16| name(addr, format!("${:04X}", addr));
+
+		match self.mode {
+			AddressingMode::Implied => String::new(),
+			AddressingMode::Accumulator => "A".to_string(),
+			AddressingMode::Immediate => format!("#${:02X}", self.operand),
+			AddressingMode::ZeroPage => zp(self.operand),
+			AddressingMode::ZeroPageX => format!("{},X", zp(self.operand)),
+			AddressingMode::ZeroPageY => format!("{},Y", zp(self.operand)),
+			AddressingMode::Relative => abs(self.target.unwrap_or(self.operand)),
+			AddressingMode::Absolute => abs(self.operand),
+			AddressingMode::AbsoluteX => format!("{},X", abs(self.operand)),
+			AddressingMode::AbsoluteY => format!("{},Y", abs(self.operand)),
+			AddressingMode::Indirect => format!("({})", abs(self.operand)),
+			AddressingMode::IndirectX => format!("({},X)", zp(self.operand)),
+			AddressingMode::IndirectY => format!("({}),Y", zp(self.operand)),
+			AddressingMode::ZeroPageIndirect => format!("({})", zp(self.operand)),
+			AddressingMode::AbsoluteIndirectX => format!("({},X)", abs(self.operand)),
+			AddressingMode::ZeroPageRelative => format!("{},{}", zp(self.operand), abs(self.target.unwrap_or(0))),
+		}
+	}
+
+	// Full text, e.g. "LDA ($20),Y", resolving labels if given
+	pub fn text(&self, labels: Option<&Labels>) -> String {
+		let operand = self.operand_text(labels);
+		if operand.is_empty() {
+			self.mnemonic.to_string()
+		} else {
+			format!("{} {}", self.mnemonic, operand)
+		}
+	}
+
+}
+
+impl fmt::Display for Instruction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.text(None))
+	}
+}
+
+
+// Decodes the single instruction at addr
+pub fn decode(mem: impl Fn(u16) -> u8, addr: u16) -> Instruction {
+	decode_for(Variant::default(), mem, addr)
+}
+
+// decode() with another chip's instruction set
+pub fn decode_for(variant: Variant, mem: impl Fn(u16) -> u8, addr: u16) -> Instruction {
+	let opcode = mem(addr);
+	let entry = &variant.opcodes()[opcode as usize];
+
+	let mut bytes = [opcode, 0, 0];
+	for (i, byte) in bytes.iter_mut().enumerate().take(entry.bytes as usize).skip(1) {
+		*byte = mem(addr.wrapping_add(i as u16));
+	}
+
+	let operand = match entry.bytes {
+		_ if entry.address_mode == AddressingMode::ZeroPageRelative => bytes[1] as u16,
+		2 => bytes[1] as u16,
+		3 => bytes[1] as u16 | ((bytes[2] as u16) << 8),
+		_ => 0,
+	};
+
+	// Branch offsets are relative to the instruction that follows
+	let target = match entry.address_mode {
+		AddressingMode::Relative => Some(addr.wrapping_add(2).wrapping_add(bytes[1] as i8 as u16)),
+		AddressingMode::ZeroPageRelative => Some(addr.wrapping_add(3).wrapping_add(bytes[2] as i8 as u16)),
+		_ => None,
+	};
+
+	Instruction {
+		addr,
+		opcode,
+		bytes,
+		len: entry.bytes,
+		mnemonic: entry.mnemonic,
+		mode: entry.address_mode,
+		official: entry.official,
+		operand,
+		target,
+	}
+}
+
+// Decodes every instruction in start..=stop, in address order. An
+// instruction cut off by stop is left out, so mem is never read past it.
+pub fn disassemble(mem: impl Fn(u16) -> u8, start: u16, stop: u16) -> Vec<Instruction> {
+	disassemble_for(Variant::default(), mem, start, stop)
+}
+
+pub fn disassemble_for(variant: Variant, mem: impl Fn(u16) -> u8, start: u16, stop: u16) -> Vec<Instruction> {
+	let mut lines = Vec::new();
+	let mut addr = start as u32;
+
+	while addr <= stop as u32 {
+		let len = variant.opcodes()[mem(addr as u16) as usize].bytes as u32;
+		if addr + len - 1 > stop as u32 {
+			break;
+		}
+
+		let instruction = decode_for(variant, &mem, addr as u16);
+		addr += instruction.len as u32;
+		lines.push(instruction);
+	}
+
+	lines
+}
+
+// Listing of a range, one "$C000: A9 20     LDA #$20" line per instruction
+pub fn listing(mem: impl Fn(u16) -> u8, start: u16, stop: u16, labels: Option<&Labels>) -> String {
+	let mut out = String::new();
+
+	for instruction in disassemble(mem, start, stop) {
+		if let Some(label) = labels.and_then(|l| l.get(&instruction.addr)) {
+			out += &format!("{}:\n", label);
+		}
+
+		let raw: Vec<String> = instruction.raw().iter().map(|b| format!("{:02X}", b)).collect();
+		out += &format!("${:04X}: {:<9} {}\n", instruction.addr, raw.join(" "), instruction.text(labels));
+	}
+
+	out
+}
@@ -0,0 +1,7 @@
+pub mod asm;
+pub mod bus;
+pub mod cartridge;
+pub mod cpu;
+pub mod disasm;
+pub mod nes;
+pub mod ppu;
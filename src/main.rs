@@ -1,10 +1,13 @@
-mod cpu;
+use nes::asm;
+use nes::bus::FlatRam;
+use nes::cpu;
 
 
 fn main() {
+    let mut ram = FlatRam::init();
     let mut cpu = cpu::Cpu::init();
-    cpu.mem[cpu.pc as usize] = 0x69;
-    cpu.mem[cpu.pc as usize +1] = 0x07;
-    cpu.cycle();
+    let program = asm::assemble(&format!(".org ${:04X}\n ADC #$07", cpu.pc)).unwrap();
+    program.load(&mut ram);
+    cpu.cycle(&mut ram);
     println!("Hello, world!");
 }
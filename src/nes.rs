@@ -0,0 +1,147 @@
+//
+// NES CPU Memory Map:
+//	https://www.nesdev.org/wiki/CPU_memory_map
+//
+//	$0000-$07FF	2 KiB internal RAM
+//	$0800-$1FFF	Mirrors of $0000-$07FF
+//	$2000-$2007	PPU registers
+//	$2008-$3FFF	Mirrors of $2000-$2007 (every 8 bytes)
+//	$4000-$4017	APU and I/O registers
+//	$4018-$401F	APU and I/O functionality that is normally disabled
+//	$4020-$FFFF	Cartridge space
+
+
+use crate::bus::Bus;
+use crate::cartridge::Cartridge;
+use crate::cpu::Cpu;
+use crate::ppu::Ppu;
+
+// System bus as seen by the 2A03
+pub struct NesBus {
+	pub ram: [u8; 2*1024],
+	pub ppu: Ppu,
+	pub apu_io: [u8; 0x20],
+	pub cart: Option<Box<dyn Cartridge>>,
+}
+
+impl NesBus {
+
+	pub fn init() -> Self {
+		Self {
+			ram: [0; 2*1024],
+			ppu: Ppu::init(),
+			apu_io: [0; 0x20],
+			cart: None,
+		}
+	}
+
+}
+
+impl Bus for NesBus {
+
+	fn read(&mut self, addr: u16) -> u8 {
+		match addr {
+			0x0000..=0x1FFF => self.ram[(addr & 0x07FF) as usize],
+			0x2000..=0x3FFF => self.ppu.cpu_read(&mut self.cart, addr),
+			0x4000..=0x401F => self.apu_io[(addr & 0x001F) as usize],
+			0x4020..=0xFFFF => match &mut self.cart {
+				Some(cart) => cart.cpu_read(addr),
+				None => 0,
+			},
+		}
+	}
+
+	fn write(&mut self, addr: u16, data: u8) {
+		match addr {
+			0x0000..=0x1FFF => self.ram[(addr & 0x07FF) as usize] = data,
+			0x2000..=0x3FFF => self.ppu.cpu_write(&mut self.cart, addr, data),
+			0x4000..=0x401F => self.apu_io[(addr & 0x001F) as usize] = data,
+			0x4020..=0xFFFF => if let Some(cart) = &mut self.cart {
+				cart.cpu_write(addr, data);
+			},
+		}
+	}
+
+	fn peek(&self, addr: u16) -> u8 {
+		match addr {
+			0x0000..=0x1FFF => self.ram[(addr & 0x07FF) as usize],
+			0x2000..=0x3FFF => self.ppu.cpu_peek(addr),
+			0x4000..=0x401F => self.apu_io[(addr & 0x001F) as usize],
+			0x4020..=0xFFFF => match &self.cart {
+				Some(cart) => cart.cpu_peek(addr),
+				None => 0,
+			},
+		}
+	}
+
+	fn ppu_position(&self) -> Option<(u16, u16)> {
+		Some((self.ppu.scanline, self.ppu.dot))
+	}
+
+}
+
+
+// The console: a 2A03 wired to the NES memory map
+pub struct Nes {
+	pub cpu: Cpu,
+	pub bus: NesBus,
+	pub system_clock: u64,		// PPU dots since power on
+}
+
+impl Nes {
+
+	pub fn init() -> Self {
+		Self {
+			cpu: Cpu::init(),
+			bus: NesBus::init(),
+			system_clock: 0,
+		}
+	}
+
+	pub fn insert_cartridge(&mut self, cart: Box<dyn Cartridge>) {
+		self.bus.cart = Some(cart);
+	}
+
+	// Cold boot: internal RAM is cleared and the CPU takes its power-up state
+	pub fn power_on(&mut self) {
+		self.bus.ram = [0; 2*1024];
+		self.bus.ppu = Ppu::init();
+		self.system_clock = 0;
+		self.cpu.power_on(&mut self.bus);
+	}
+
+	// Reset button: RAM and CPU registers survive
+	pub fn reset(&mut self) {
+		self.bus.ppu.reset();
+		self.cpu.reset(&mut self.bus);
+	}
+
+	// One PPU dot. The CPU runs at a third of the PPU's speed, and its NMI
+	// input follows the PPU's output.
+	pub fn clock(&mut self) {
+		if self.system_clock.is_multiple_of(3) {
+			self.cpu.cycle(&mut self.bus);
+		}
+		self.bus.ppu.clock(&mut self.bus.cart);
+		self.cpu.set_nmi(self.bus.ppu.nmi());
+
+		self.system_clock += 1;
+	}
+
+	// Clocks the whole system until the CPU has finished one instruction (or
+	// interrupt sequence)
+	pub fn step_instruction(&mut self) {
+		while !self.cpu.at_boundary() && !self.cpu.is_halted() {
+			self.clock();
+		}
+
+		let start = self.cpu.global_clock;
+		while self.cpu.global_clock == start || !self.cpu.at_boundary() {
+			self.clock();
+			if self.cpu.is_halted() {
+				break;
+			}
+		}
+	}
+
+}
@@ -0,0 +1,154 @@
+// Background rendering
+//	https://www.nesdev.org/wiki/PPU_rendering
+//
+// Every 8 dots the PPU fetches the next tile's nametable byte, attribute
+// byte and the two pattern planes, then moves v one tile right. The fetched
+// tile is loaded into the low byte of 16 bit shift registers, which shift
+// one bit per dot; fine X picks the bit that becomes the pixel.
+//
+//	Dots 1-256		fetch the tiles of this line (from the third one on)
+//	Dot 256			v moves down a line
+//	Dot 257			horizontal scroll bits reloaded from t
+//	Dots 280-304	vertical scroll bits reloaded from t, pre-render line only
+//	Dots 321-336	fetch the first two tiles of the next line
+//	Dots 337-340	two unused nametable fetches
+
+
+use super::*;
+
+impl Ppu {
+
+	// Fetches and scroll updates for one dot of a visible or pre-render line
+	pub(super) fn background_fetch(&mut self, cart: &mut Option<Box<dyn Cartridge>>) {
+		let dot = self.dot;
+
+		if (2..=257).contains(&dot) || (321..=337).contains(&dot) {
+			self.update_background_shifters();
+
+			match (dot - 1) % 8 {
+				0 => {
+					self.load_background_shifters();
+					self.bg_next_tile_id = self.ppu_read(cart, 0x2000 | (self.vram_addr & 0x0FFF));
+				}
+				2 => {
+					// One attribute byte covers 4x4 tiles, two bits per 2x2
+					let v = self.vram_addr;
+					let attrib = self.ppu_read(cart, 0x23C0 | (v & 0x0C00) | ((v >> 4) & 0x0038) | ((v >> 2) & 0x0007));
+					let shift = ((v >> 4) & 0x04) | (v & 0x02);
+					self.bg_next_tile_attrib = (attrib >> shift) & 0x03;
+				}
+				4 => {
+					let addr = self.pattern_addr();
+					self.bg_next_tile_lsb = self.ppu_read(cart, addr);
+				}
+				6 => {
+					let addr = self.pattern_addr() + 8;
+					self.bg_next_tile_msb = self.ppu_read(cart, addr);
+				}
+				7 => self.increment_scroll_x(),
+				_ => (),
+			}
+		}
+
+		if dot == 256 {
+			self.increment_scroll_y();
+		}
+		if dot == 257 {
+			self.transfer_address_x();
+		}
+		if dot == 338 || dot == 340 {
+			self.bg_next_tile_id = self.ppu_read(cart, 0x2000 | (self.vram_addr & 0x0FFF));
+		}
+		if self.scanline == PRE_RENDER_SCANLINE && (280..=304).contains(&dot) {
+			self.transfer_address_y();
+		}
+	}
+
+	// Background pixel (0-3) and palette (0-3) at screen column x
+	pub(super) fn background_pixel(&self, x: usize) -> (u8, u8) {
+		if self.mask & MASK_BACKGROUND == 0 || (x < 8 && self.mask & MASK_BACKGROUND_LEFT == 0) {
+			return (0, 0);
+		}
+
+		let bit = 0x8000 >> self.fine_x;
+		let pixel = ((self.bg_shifter_pattern_hi & bit != 0) as u8) << 1 | (self.bg_shifter_pattern_lo & bit != 0) as u8;
+		let palette = ((self.bg_shifter_attrib_hi & bit != 0) as u8) << 1 | (self.bg_shifter_attrib_lo & bit != 0) as u8;
+
+		(pixel, palette)
+	}
+
+	// Low plane of the next tile's current row; the high plane is 8 bytes on
+	fn pattern_addr(&self) -> u16 {
+		let table = if self.ctrl & CTRL_BACKGROUND_TABLE != 0 { 0x1000 } else { 0x0000 };
+		let fine_y = (self.vram_addr >> 12) & 0x07;
+		table + ((self.bg_next_tile_id as u16) << 4) + fine_y
+	}
+
+	fn update_background_shifters(&mut self) {
+		if self.mask & MASK_BACKGROUND != 0 {
+			self.bg_shifter_pattern_lo <<= 1;
+			self.bg_shifter_pattern_hi <<= 1;
+			self.bg_shifter_attrib_lo <<= 1;
+			self.bg_shifter_attrib_hi <<= 1;
+		}
+	}
+
+	// The next tile goes into the low byte; the attribute bits are spread
+	// over all 8 pixels
+	fn load_background_shifters(&mut self) {
+		self.bg_shifter_pattern_lo = (self.bg_shifter_pattern_lo & 0xFF00) | self.bg_next_tile_lsb as u16;
+		self.bg_shifter_pattern_hi = (self.bg_shifter_pattern_hi & 0xFF00) | self.bg_next_tile_msb as u16;
+
+		let lo = if self.bg_next_tile_attrib & 0x01 != 0 { 0xFF } else { 0x00 };
+		let hi = if self.bg_next_tile_attrib & 0x02 != 0 { 0xFF } else { 0x00 };
+		self.bg_shifter_attrib_lo = (self.bg_shifter_attrib_lo & 0xFF00) | lo;
+		self.bg_shifter_attrib_hi = (self.bg_shifter_attrib_hi & 0xFF00) | hi;
+	}
+
+
+	//
+	// Scroll Increments
+
+	// Coarse X, into the horizontally adjacent nametable after tile 31
+	fn increment_scroll_x(&mut self) {
+		if self.vram_addr & 0x001F == 31 {
+			self.vram_addr &= !0x001F;
+			self.vram_addr ^= 0x0400;
+		} else {
+			self.vram_addr += 1;
+		}
+	}
+
+	// Fine Y, then coarse Y. Row 29 is the last of a nametable, so it moves
+	// into the vertically adjacent one; rows 30 and 31 (attribute memory,
+	// only reachable by writing them) wrap within the same nametable.
+	fn increment_scroll_y(&mut self) {
+		if self.vram_addr & 0x7000 != 0x7000 {
+			self.vram_addr += 0x1000;
+			return;
+		}
+
+		self.vram_addr &= !0x7000;
+		let mut coarse_y = (self.vram_addr & 0x03E0) >> 5;
+		match coarse_y {
+			29 => {
+				coarse_y = 0;
+				self.vram_addr ^= 0x0800;
+			}
+			31 => coarse_y = 0,
+			_ => coarse_y += 1,
+		}
+		self.vram_addr = (self.vram_addr & !0x03E0) | (coarse_y << 5);
+	}
+
+	// Coarse X and the horizontal nametable bit from t
+	fn transfer_address_x(&mut self) {
+		self.vram_addr = (self.vram_addr & !0x041F) | (self.tram_addr & 0x041F);
+	}
+
+	// Fine Y, coarse Y and the vertical nametable bit from t
+	fn transfer_address_y(&mut self) {
+		self.vram_addr = (self.vram_addr & !0x7BE0) | (self.tram_addr & 0x7BE0);
+	}
+
+}
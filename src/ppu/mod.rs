@@ -1,5 +1,407 @@
+//
 // 2C02 PPU
-pub struct ppu {
-    pub mem: [u8; 64*1024],
-    pub oam: [u8; 256]
-}
\ No newline at end of file
+//	https://www.nesdev.org/wiki/PPU_registers
+//
+// The CPU sees eight registers at $2000-$2007, mirrored every 8 bytes up to
+// $3FFF:
+//
+//	$2000	PPUCTRL		write
+//	$2001	PPUMASK		write
+//	$2002	PPUSTATUS	read, clears vblank and the write toggle
+//	$2003	OAMADDR		write
+//	$2004	OAMDATA		read/write, writes increment OAMADDR
+//	$2005	PPUSCROLL	write twice: X then Y
+//	$2006	PPUADDR		write twice: high then low byte
+//	$2007	PPUDATA		read/write, increments the address by 1 or 32
+//
+// Registers that cannot be read return whatever was last driven onto the
+// PPU's data bus.
+//
+// PPU Memory Map:
+//	https://www.nesdev.org/wiki/PPU_memory_map
+//
+//	$0000-$1FFF	Pattern tables, on the cartridge
+//	$2000-$2FFF	Four nametables in 2 KiB of VRAM, mirrored as the cartridge says
+//	$3000-$3EFF	Mirror of $2000-$2EFF
+//	$3F00-$3F1F	Palette RAM; $3F10/$3F14/$3F18/$3F1C mirror $3F00/$3F04/$3F08/$3F0C
+//	$3F20-$3FFF	Mirrors of $3F00-$3F1F
+//
+// NTSC Frame Timing:
+//	https://www.nesdev.org/wiki/PPU_frame_timing
+//
+//	341 dots per scanline, three per CPU cycle
+//	Scanlines 0-239		visible
+//	Scanline 240		post-render, idle
+//	Scanlines 241-260	vertical blank, flag set at 241 dot 1
+//	Scanline 261		pre-render, flags cleared at dot 1; with rendering
+//						enabled it is one dot shorter on odd frames
+//
+// Scrolling works through the internal registers described in "The skinny on
+// NES scrolling" (loopy):
+//	https://www.nesdev.org/wiki/PPU_scrolling
+//
+//	v, t	yyy NN YYYYY XXXXX: fine Y, nametable, coarse Y, coarse X
+//	x		fine X, 3 bits
+//	w		first/second write toggle
+
+
+mod background;
+mod sprites;
+
+use crate::cartridge::{Cartridge, Mirroring};
+
+
+// PPUCTRL
+pub const CTRL_NAMETABLE: u8 = 0x03;		// Base nametable, bits 10-11 of t
+pub const CTRL_INCREMENT_32: u8 = 0x04;		// PPUDATA steps down a row instead of across
+pub const CTRL_SPRITE_TABLE: u8 = 0x08;		// 8x8 sprite tiles from $1000 rather than $0000
+pub const CTRL_BACKGROUND_TABLE: u8 = 0x10;	// Background tiles from $1000 rather than $0000
+pub const CTRL_SPRITE_8X16: u8 = 0x20;
+pub const CTRL_NMI: u8 = 0x80;				// NMI at the start of vblank
+
+// PPUMASK
+pub const MASK_GREYSCALE: u8 = 0x01;
+pub const MASK_BACKGROUND_LEFT: u8 = 0x02;	// Background in the leftmost 8 pixels
+pub const MASK_SPRITES_LEFT: u8 = 0x04;		// Sprites in the leftmost 8 pixels
+pub const MASK_BACKGROUND: u8 = 0x08;
+pub const MASK_SPRITES: u8 = 0x10;
+
+// PPUSTATUS
+pub const STATUS_SPRITE_OVERFLOW: u8 = 0x20;
+pub const STATUS_SPRITE_ZERO_HIT: u8 = 0x40;
+pub const STATUS_VBLANK: u8 = 0x80;
+
+pub const DOTS_PER_SCANLINE: u16 = 341;
+pub const SCANLINES_PER_FRAME: u16 = 262;
+pub const VBLANK_SCANLINE: u16 = 241;
+pub const PRE_RENDER_SCANLINE: u16 = 261;
+
+pub const SCREEN_WIDTH: usize = 256;
+pub const SCREEN_HEIGHT: usize = 240;
+
+pub struct Ppu {
+	pub ctrl: u8,
+	pub mask: u8,
+	pub status: u8,
+	pub oam_addr: u8,
+	pub oam: [u8; 256],
+	pub vram: [u8; 4*1024],		// 2 KiB in the console; the rest stands in for four-screen cartridge RAM
+	pub palette: [u8; 32],
+
+	pub vram_addr: u16,			// v: current address, and the scroll position while rendering
+	pub tram_addr: u16,			// t: scroll and address writes collect here before reaching v
+	pub fine_x: u8,				// x
+	pub address_latch: bool,	// w: set after the first PPUSCROLL/PPUADDR write
+	pub data_buffer: u8,		// PPUDATA read buffer
+	pub io_latch: u8,			// Last value on the register data bus
+
+	pub scanline: u16,			// 0-261, 261 being the pre-render line
+	pub dot: u16,				// 0-340
+	pub frame: u64,				// Frames completed since power on
+
+	// Background fetches for the next tile, and the shift registers holding
+	// the current two tiles
+	pub bg_next_tile_id: u8,
+	pub bg_next_tile_attrib: u8,
+	pub bg_next_tile_lsb: u8,
+	pub bg_next_tile_msb: u8,
+	pub bg_shifter_pattern_lo: u16,
+	pub bg_shifter_pattern_hi: u16,
+	pub bg_shifter_attrib_lo: u16,
+	pub bg_shifter_attrib_hi: u16,
+
+	// Sprites found for the next line, and the 8 slots drawing this one
+	pub secondary_oam: [u8; 32],
+	pub sprite_count: u8,
+	pub sprite_zero_next: bool,		// Sprite 0 is in secondary OAM
+	pub sprite_zero_on_line: bool,	// ... and so in slot 0 for this line
+	pub sprite_pattern_lo: [u8; 8],
+	pub sprite_pattern_hi: [u8; 8],
+	pub sprite_attrib: [u8; 8],
+	pub sprite_x: [u8; 8],			// Dots left until the sprite starts
+
+	pub screen: [u8; SCREEN_WIDTH*SCREEN_HEIGHT],	// Colour (palette RAM value, 0-63) of every pixel
+}
+
+impl Ppu {
+
+	pub fn init() -> Self {
+		Self {
+			ctrl: 0x00,
+			mask: 0x00,
+			status: 0x00,
+			oam_addr: 0x00,
+			oam: [0; 256],
+			vram: [0; 4*1024],
+			palette: [0; 32],
+
+			vram_addr: 0x0000,
+			tram_addr: 0x0000,
+			fine_x: 0x00,
+			address_latch: false,
+			data_buffer: 0x00,
+			io_latch: 0x00,
+
+			scanline: 0,
+			dot: 0,
+			frame: 0,
+
+			bg_next_tile_id: 0x00,
+			bg_next_tile_attrib: 0x00,
+			bg_next_tile_lsb: 0x00,
+			bg_next_tile_msb: 0x00,
+			bg_shifter_pattern_lo: 0x0000,
+			bg_shifter_pattern_hi: 0x0000,
+			bg_shifter_attrib_lo: 0x0000,
+			bg_shifter_attrib_hi: 0x0000,
+
+			secondary_oam: [0xFF; 32],
+			sprite_count: 0,
+			sprite_zero_next: false,
+			sprite_zero_on_line: false,
+			sprite_pattern_lo: [0; 8],
+			sprite_pattern_hi: [0; 8],
+			sprite_attrib: [0; 8],
+			sprite_x: [0xFF; 8],
+
+			screen: [0; SCREEN_WIDTH*SCREEN_HEIGHT],
+		}
+	}
+
+	// Reset line: PPUCTRL, PPUMASK, scroll and the write toggle are cleared.
+	// PPUSTATUS, OAMADDR and PPUADDR keep their values.
+	pub fn reset(&mut self) {
+		self.ctrl = 0x00;
+		self.mask = 0x00;
+		self.tram_addr = 0x0000;
+		self.fine_x = 0x00;
+		self.address_latch = false;
+		self.data_buffer = 0x00;
+	}
+
+
+	//
+	// Timing
+
+	// One dot
+	pub fn clock(&mut self, cart: &mut Option<Box<dyn Cartridge>>) {
+		if self.rendering() && (self.scanline < SCREEN_HEIGHT as u16 || self.scanline == PRE_RENDER_SCANLINE) {
+			self.background_fetch(cart);
+			self.sprite_fetch(cart);
+		}
+		if self.scanline < SCREEN_HEIGHT as u16 && (1..=SCREEN_WIDTH as u16).contains(&self.dot) {
+			self.draw_pixel(cart);
+		}
+
+		if self.dot == 1 {
+			match self.scanline {
+				VBLANK_SCANLINE => self.status |= STATUS_VBLANK,
+				PRE_RENDER_SCANLINE => self.status &= !(STATUS_VBLANK | STATUS_SPRITE_ZERO_HIT | STATUS_SPRITE_OVERFLOW),
+				_ => (),
+			}
+		}
+
+		self.dot += 1;
+
+		// Odd frames jump from the pre-render line's dot 339 straight to 0, 0
+		if self.scanline == PRE_RENDER_SCANLINE && self.dot == DOTS_PER_SCANLINE - 1 && self.frame % 2 == 1 && self.rendering() {
+			self.dot = DOTS_PER_SCANLINE;
+		}
+
+		if self.dot == DOTS_PER_SCANLINE {
+			self.dot = 0;
+			self.scanline += 1;
+			if self.scanline == SCANLINES_PER_FRAME {
+				self.scanline = 0;
+				self.frame += 1;
+			}
+		}
+	}
+
+	// Level of the /NMI output: asserted through vblank while PPUCTRL enables it
+	pub fn nmi(&self) -> bool {
+		self.status & STATUS_VBLANK != 0 && self.ctrl & CTRL_NMI != 0
+	}
+
+	pub fn rendering(&self) -> bool {
+		self.mask & (MASK_BACKGROUND | MASK_SPRITES) != 0
+	}
+
+	// Colour of the pixel for this dot of a visible line
+	fn draw_pixel(&mut self, cart: &mut Option<Box<dyn Cartridge>>) {
+		let x = (self.dot - 1) as usize;
+		let y = self.scanline as usize;
+
+		let (bg_pixel, bg_palette) = self.background_pixel(x);
+		let (fg_pixel, fg_palette, behind, sprite_zero) = self.sprite_pixel(x);
+
+		// Sprite 0 hits where it overlaps an opaque background pixel, except
+		// in the last column
+		if sprite_zero && bg_pixel != 0 && fg_pixel != 0 && x != SCREEN_WIDTH - 1 {
+			self.status |= STATUS_SPRITE_ZERO_HIT;
+		}
+
+		let (pixel, palette) = match (bg_pixel, fg_pixel) {
+			(0, 0) => (0, 0),
+			(0, _) => (fg_pixel, fg_palette),
+			(_, 0) => (bg_pixel, bg_palette),
+			_ if behind => (bg_pixel, bg_palette),
+			_ => (fg_pixel, fg_palette),
+		};
+
+		// Pixel 0 of every palette shows the backdrop colour at $3F00
+		let entry = if pixel == 0 { 0x00 } else { (palette << 2) | pixel };
+		let mut colour = self.ppu_read(cart, 0x3F00 | entry as u16);
+		if self.mask & MASK_GREYSCALE != 0 {
+			colour &= 0x30;
+		}
+
+		self.screen[y*SCREEN_WIDTH + x] = colour;
+	}
+
+
+	//
+	// CPU Interface
+
+	// The PPU does not own the cartridge, so anything that may reach the
+	// pattern tables or nametables is handed the cartridge slot
+
+	pub fn cpu_read(&mut self, cart: &mut Option<Box<dyn Cartridge>>, addr: u16) -> u8 {
+		let data = match addr & 0x0007 {
+			0x0002 => {
+				let data = self.cpu_peek(addr);
+				self.status &= !STATUS_VBLANK;
+				self.address_latch = false;
+				data
+			}
+			0x0004 => self.oam[self.oam_addr as usize],
+			0x0007 => {
+				// Reads come out of a buffer that is refilled afterwards, so
+				// they lag one behind. Palette reads are immediate, but still
+				// refill the buffer from the nametable "underneath".
+				let mut data = self.data_buffer;
+				self.data_buffer = self.ppu_read(cart, self.vram_addr);
+				if self.vram_addr & 0x3FFF >= 0x3F00 {
+					data = self.ppu_read(cart, self.vram_addr);
+					self.data_buffer = self.ppu_read(cart, self.vram_addr & 0x2FFF);
+				}
+				self.increment_vram_addr();
+				data
+			}
+			_ => self.io_latch,
+		};
+
+		self.io_latch = data;
+		data
+	}
+
+	pub fn cpu_write(&mut self, cart: &mut Option<Box<dyn Cartridge>>, addr: u16, data: u8) {
+		self.io_latch = data;
+
+		match addr & 0x0007 {
+			0x0000 => {
+				self.ctrl = data;
+				self.tram_addr = (self.tram_addr & !0x0C00) | ((data & CTRL_NAMETABLE) as u16) << 10;
+			}
+			0x0001 => self.mask = data,
+			0x0002 => (),
+			0x0003 => self.oam_addr = data,
+			0x0004 => {
+				self.oam[self.oam_addr as usize] = data;
+				self.oam_addr = self.oam_addr.wrapping_add(1);
+			}
+			0x0005 => {
+				if !self.address_latch {
+					self.fine_x = data & 0x07;
+					self.tram_addr = (self.tram_addr & !0x001F) | (data >> 3) as u16;
+				} else {
+					self.tram_addr = (self.tram_addr & !0x73E0) | ((data & 0x07) as u16) << 12 | ((data >> 3) as u16) << 5;
+				}
+				self.address_latch = !self.address_latch;
+			}
+			0x0006 => {
+				if !self.address_latch {
+					// Bit 14 of t is cleared along the way
+					self.tram_addr = ((data as u16 & 0x3F) << 8) | (self.tram_addr & 0x00FF);
+				} else {
+					self.tram_addr = (self.tram_addr & 0xFF00) | data as u16;
+					self.vram_addr = self.tram_addr;
+				}
+				self.address_latch = !self.address_latch;
+			}
+			_ => {
+				self.ppu_write(cart, self.vram_addr, data);
+				self.increment_vram_addr();
+			}
+		}
+	}
+
+	// What cpu_read would return, without side effects
+	pub fn cpu_peek(&self, addr: u16) -> u8 {
+		match addr & 0x0007 {
+			// The low bits are not driven and keep the stale bus value
+			0x0002 => (self.status & 0xE0) | (self.io_latch & 0x1F),
+			0x0004 => self.oam[self.oam_addr as usize],
+			0x0007 if self.vram_addr & 0x3FFF >= 0x3F00 => self.palette[palette_index(self.vram_addr)] & 0x3F,
+			0x0007 => self.data_buffer,
+			_ => self.io_latch,
+		}
+	}
+
+	fn increment_vram_addr(&mut self) {
+		let step = if self.ctrl & CTRL_INCREMENT_32 != 0 { 32 } else { 1 };
+		self.vram_addr = self.vram_addr.wrapping_add(step) & 0x7FFF;
+	}
+
+
+	//
+	// PPU Address Space
+
+	pub fn ppu_read(&self, cart: &mut Option<Box<dyn Cartridge>>, addr: u16) -> u8 {
+		match addr & 0x3FFF {
+			addr @ 0x0000..=0x1FFF => cart.as_mut().map_or(0, |cart| cart.ppu_read(addr)),
+			addr @ 0x2000..=0x3EFF => self.vram[nametable_index(mirroring(cart), addr)],
+			addr => self.palette[palette_index(addr)] & 0x3F,
+		}
+	}
+
+	pub fn ppu_write(&mut self, cart: &mut Option<Box<dyn Cartridge>>, addr: u16, data: u8) {
+		match addr & 0x3FFF {
+			addr @ 0x0000..=0x1FFF => if let Some(cart) = cart {
+				cart.ppu_write(addr, data);
+			},
+			addr @ 0x2000..=0x3EFF => self.vram[nametable_index(mirroring(cart), addr)] = data,
+			addr => self.palette[palette_index(addr)] = data,
+		}
+	}
+
+}
+
+// With the slot empty the nametables are simply mirrored horizontally
+fn mirroring(cart: &Option<Box<dyn Cartridge>>) -> Mirroring {
+	cart.as_ref().map_or(Mirroring::Horizontal, |cart| cart.mirroring())
+}
+
+// Offset into vram of a nametable address ($2000-$3EFF)
+fn nametable_index(mirroring: Mirroring, addr: u16) -> usize {
+	let table = (addr >> 10) & 0x0003;
+	let bank = match mirroring {
+		Mirroring::Horizontal => table >> 1,
+		Mirroring::Vertical => table & 0x0001,
+		Mirroring::OneScreenLo => 0,
+		Mirroring::OneScreenHi => 1,
+		Mirroring::FourScreen => table,
+	};
+
+	(bank * 0x0400 + (addr & 0x03FF)) as usize
+}
+
+// Offset into palette RAM; the sprite palettes' backdrop entries are the
+// background ones
+fn palette_index(addr: u16) -> usize {
+	let index = addr & 0x001F;
+	match index {
+		0x10 | 0x14 | 0x18 | 0x1C => (index & 0x000F) as usize,
+		_ => index as usize,
+	}
+}
@@ -0,0 +1,163 @@
+// Sprite evaluation and rendering
+//	https://www.nesdev.org/wiki/PPU_sprite_evaluation
+//	https://www.nesdev.org/wiki/PPU_OAM
+//
+// OAM holds 64 sprites of 4 bytes: Y (top - 1), tile, attributes, X. While
+// a line is drawn the PPU looks for the sprites on it in OAM and copies the
+// first 8 into secondary OAM; they are what the next line shows, which is
+// why Y is one less than the first line a sprite appears on.
+//
+//	Dots 1-64		secondary OAM cleared to $FF
+//	Dots 65-256		evaluation, done at once on dot 256 here
+//	Dots 257-320	pattern fetches for the 8 slots, 8 dots each; OAMADDR
+//					is held at 0
+//
+// Each slot gets two pattern shift registers and a down counter loaded
+// with X. The counter runs out at the sprite's left edge and the registers
+// then shift out one pixel per dot. Lower slots win where sprites overlap.
+//
+// Evaluation sets the overflow flag on a ninth sprite, without the
+// hardware's diagonal OAM scan bug.
+
+
+use super::*;
+
+// Attribute byte
+const ATTRIB_PALETTE: u8 = 0x03;
+const ATTRIB_BEHIND_BACKGROUND: u8 = 0x20;
+const ATTRIB_FLIP_H: u8 = 0x40;
+const ATTRIB_FLIP_V: u8 = 0x80;
+
+impl Ppu {
+
+	// Evaluation and pattern fetches for one dot of a visible or pre-render
+	// line. Nothing is evaluated on the pre-render line, so no sprites ever
+	// show on line 0.
+	pub(super) fn sprite_fetch(&mut self, cart: &mut Option<Box<dyn Cartridge>>) {
+		let dot = self.dot;
+		let visible = self.scanline < SCREEN_HEIGHT as u16;
+
+		if dot == 64 {
+			self.secondary_oam = [0xFF; 32];
+			self.sprite_count = 0;
+			self.sprite_zero_next = false;
+		}
+		if dot == 256 && visible {
+			self.evaluate_sprites();
+		}
+
+		if (257..=320).contains(&dot) {
+			self.oam_addr = 0x00;
+
+			let slot = ((dot - 257) / 8) as usize;
+			match (dot - 257) % 8 {
+				2 => self.sprite_attrib[slot] = self.secondary_oam[slot*4 + 2],
+				3 => self.sprite_x[slot] = self.secondary_oam[slot*4 + 3],
+				4 => self.sprite_pattern_lo[slot] = self.sprite_pattern(cart, slot, 0),
+				6 => self.sprite_pattern_hi[slot] = self.sprite_pattern(cart, slot, 8),
+				_ => (),
+			}
+
+			if dot == 257 {
+				self.sprite_zero_on_line = self.sprite_zero_next;
+			}
+		}
+	}
+
+	// Sprite pixel (0-3), palette (4-7), whether it sits behind the
+	// background and whether it comes from sprite 0, at screen column x
+	pub(super) fn sprite_pixel(&mut self, x: usize) -> (u8, u8, bool, bool) {
+		if self.rendering() && x > 0 {
+			self.update_sprite_shifters();
+		}
+
+		if self.mask & MASK_SPRITES == 0 || (x < 8 && self.mask & MASK_SPRITES_LEFT == 0) {
+			return (0, 0, false, false);
+		}
+
+		for slot in 0..8 {
+			if self.sprite_x[slot] != 0 {
+				continue;
+			}
+
+			let pixel = (self.sprite_pattern_hi[slot] & 0x80) >> 6 | (self.sprite_pattern_lo[slot] & 0x80) >> 7;
+			if pixel != 0 {
+				let attrib = self.sprite_attrib[slot];
+				let palette = (attrib & ATTRIB_PALETTE) + 4;
+				return (pixel, palette, attrib & ATTRIB_BEHIND_BACKGROUND != 0, slot == 0 && self.sprite_zero_on_line);
+			}
+		}
+
+		(0, 0, false, false)
+	}
+
+	fn sprite_height(&self) -> u16 {
+		if self.ctrl & CTRL_SPRITE_8X16 != 0 { 16 } else { 8 }
+	}
+
+	// Copies the sprites on this line (shown on the next) into secondary OAM
+	fn evaluate_sprites(&mut self) {
+		let height = self.sprite_height();
+
+		for n in 0..64 {
+			let y = self.oam[n*4] as u16;
+			if self.scanline < y || self.scanline - y >= height {
+				continue;
+			}
+
+			if self.sprite_count == 8 {
+				self.status |= STATUS_SPRITE_OVERFLOW;
+				break;
+			}
+
+			let count = self.sprite_count as usize;
+			self.secondary_oam[count*4..count*4 + 4].copy_from_slice(&self.oam[n*4..n*4 + 4]);
+			if n == 0 {
+				self.sprite_zero_next = true;
+			}
+			self.sprite_count += 1;
+		}
+	}
+
+	// One pattern plane of a slot's row on the next line. Empty slots still
+	// fetch (tile $FF) but come out transparent.
+	fn sprite_pattern(&self, cart: &mut Option<Box<dyn Cartridge>>, slot: usize, plane: u16) -> u8 {
+		let [y, tile, attrib, _] = [0, 1, 2, 3].map(|i| self.secondary_oam[slot*4 + i]);
+		let height = self.sprite_height();
+
+		let mut row = self.scanline.wrapping_sub(y as u16) & (height - 1);
+		if attrib & ATTRIB_FLIP_V != 0 {
+			row = height - 1 - row;
+		}
+
+		// 8x16 sprites pick their table with bit 0 of the tile number and
+		// use an even/odd pair of tiles, top then bottom
+		let addr = if height == 16 {
+			let table = (tile as u16 & 0x01) << 12;
+			let tile = (tile as u16 & 0xFE) + (row >> 3);
+			table | (tile << 4) | (row & 0x07)
+		} else {
+			let table = if self.ctrl & CTRL_SPRITE_TABLE != 0 { 0x1000 } else { 0x0000 };
+			table | ((tile as u16) << 4) | row
+		};
+
+		let data = self.ppu_read(cart, addr + plane);
+		if slot >= self.sprite_count as usize {
+			return 0x00;
+		}
+
+		if attrib & ATTRIB_FLIP_H != 0 { data.reverse_bits() } else { data }
+	}
+
+	fn update_sprite_shifters(&mut self) {
+		for slot in 0..8 {
+			if self.sprite_x[slot] > 0 {
+				self.sprite_x[slot] -= 1;
+			} else {
+				self.sprite_pattern_lo[slot] <<= 1;
+				self.sprite_pattern_hi[slot] <<= 1;
+			}
+		}
+	}
+
+}
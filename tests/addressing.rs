@@ -0,0 +1,203 @@
+// Pointer wraparound in the indirect addressing modes
+//
+// The NMOS 6502 never carries into the high byte when it steps to the
+// second byte of a pointer: JMP ($xxFF) takes its high byte from $xx00,
+// and (zp,X) / (zp),Y pointers wrap from $FF back to $00. The program
+// counter wraps from $FFFF to $0000. Every case runs in both cores.
+
+
+mod common;
+
+use common::{boot_variant, each_mode, run};
+use nes::bus::FlatRam;
+use nes::cpu::{Cpu, StepMode, Variant};
+
+// Runs instructions of source in each core, then checks the result
+fn run_each_mode(source: &str, instructions: usize, check: impl Fn(&Cpu, &FlatRam)) {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_variant(Variant::Ricoh2A03, mode, source);
+		run(&mut cpu, &mut ram, instructions);
+		check(&cpu, &ram);
+	});
+}
+
+// Pointer at $02FF: low byte $34 there, high byte $12 at $0200 and $56 at $0300
+const JMP_PAGE_BUG: &str = "
+	.org $0200
+	.byte $12
+	.org $02FF
+	.byte $34, $56
+	.org $0400
+start:
+	JMP ($02FF)
+";
+
+#[test]
+fn jmp_indirect_page_wrap() {
+	run_each_mode(JMP_PAGE_BUG, 1, |cpu, _| assert_eq!(cpu.pc, 0x1234, "{:?}", cpu.mode));
+}
+
+#[test]
+fn jmp_indirect_65c02_crosses_page() {
+	let (mut cpu, mut ram) = boot_variant(Variant::Wdc65C02, StepMode::Instruction, JMP_PAGE_BUG);
+	run(&mut cpu, &mut ram, 1);
+	assert_eq!(cpu.pc, 0x5634);
+}
+
+#[test]
+fn jmp_indirect_trace_follows_variant() {
+	for (variant, line) in [(Variant::Ricoh2A03, "JMP ($02FF) = 1234"), (Variant::Wdc65C02, "JMP ($02FF) = 5634")] {
+		let (cpu, ram) = boot_variant(variant, StepMode::Instruction, JMP_PAGE_BUG);
+		let trace = cpu.trace_line(&ram);
+		assert!(trace.contains(line), "{:?}: {}", variant, trace);
+	}
+}
+
+#[test]
+fn jmp_indirect_within_page() {
+	let source = "
+		.org $0280
+		.word $C0DE
+		.org $0400
+	start:
+		JMP ($0280)
+	";
+	run_each_mode(source, 1, |cpu, _| assert_eq!(cpu.pc, 0xC0DE, "{:?}", cpu.mode));
+}
+
+#[test]
+fn indexed_indirect_wraps_in_zero_page() {
+	// $80 + X = $FF: pointer low byte at $FF, high byte at $00 (not $0100)
+	let source = "
+		.org $0400
+		LDA #$00
+		STA $FF
+		LDA #$03
+		STA $00
+		LDA #$06
+		STA $0100
+		LDA #$AA
+		STA $0300
+		LDA #$BB
+		STA $0600
+		LDX #$7F
+		LDA ($80,X)
+	";
+	run_each_mode(source, 12, |cpu, _| assert_eq!(cpu.ac, 0xAA, "{:?}", cpu.mode));
+}
+
+#[test]
+fn indexed_indirect_index_wraps() {
+	// $F0 + X = $0110 wraps to pointer $10
+	let source = "
+		.org $0400
+		LDA #$00
+		STA $10
+		LDA #$03
+		STA $11
+		LDA #$5A
+		STA $0300
+		LDX #$20
+		LDA ($F0,X)
+	";
+	run_each_mode(source, 8, |cpu, _| assert_eq!(cpu.ac, 0x5A, "{:?}", cpu.mode));
+}
+
+#[test]
+fn indirect_indexed_wraps_in_zero_page() {
+	// Pointer at $FF: low byte $00 there, high byte $03 at $00 (not $06 at $0100)
+	let source = "
+		.org $0400
+		LDA #$00
+		STA $FF
+		LDA #$03
+		STA $00
+		LDA #$06
+		STA $0100
+		LDA #$77
+		LDY #$05
+		STA ($FF),Y
+		LDA #$00
+		LDA ($FF),Y
+	";
+	run_each_mode(source, 11, |cpu, ram| {
+		assert_eq!(ram.mem[0x0305], 0x77, "{:?}", cpu.mode);
+		assert_eq!(ram.mem[0x0605], 0x00, "{:?}", cpu.mode);
+		assert_eq!(cpu.ac, 0x77, "{:?}", cpu.mode);
+	});
+}
+
+#[test]
+fn indirect_indexed_wraps_past_ffff() {
+	// $FFF0 + Y = $0010
+	let source = "
+		.org $0400
+		LDA #$F0
+		STA $20
+		LDA #$FF
+		STA $21
+		LDA #$99
+		STA $10
+		LDA #$00
+		LDY #$20
+		LDA ($20),Y
+	";
+	run_each_mode(source, 9, |cpu, _| assert_eq!(cpu.ac, 0x99, "{:?}", cpu.mode));
+}
+
+
+//
+// Program Counter
+
+// Runs one instruction from pc, with bytes placed there and wrapping past
+// $FFFF, and returns the CPU from each core
+fn run_at(pc: u16, bytes: &[u8], setup: impl Fn(&mut Cpu, &mut FlatRam)) -> Vec<(Cpu, FlatRam)> {
+	[StepMode::Instruction, StepMode::Cycle].into_iter().map(|mode| {
+		let mut ram = FlatRam::init();
+		for (i, &byte) in bytes.iter().enumerate() {
+			ram.mem[pc.wrapping_add(i as u16) as usize] = byte;
+		}
+
+		let mut cpu = Cpu::init();
+		cpu.mode = mode;
+		cpu.pc = pc;
+		setup(&mut cpu, &mut ram);
+
+		cpu.step_instruction(&mut ram);
+		(cpu, ram)
+	}).collect()
+}
+
+#[test]
+fn pc_wraps_past_ffff() {
+	for (cpu, _) in run_at(0xFFFF, &[0xEA], |_, _| ()) {
+		assert_eq!(cpu.pc, 0x0000, "NOP {:?}", cpu.mode);
+	}
+
+	// Operand bytes at $FFFF and $0000
+	for (cpu, _) in run_at(0xFFFE, &[0xAD, 0x00, 0x03], |_, ram| ram.mem[0x0300] = 0x42) {
+		assert_eq!((cpu.pc, cpu.ac), (0x0001, 0x42), "LDA abs {:?}", cpu.mode);
+	}
+	for (cpu, _) in run_at(0xFFFF, &[0xA9, 0x42], |_, _| ()) {
+		assert_eq!((cpu.pc, cpu.ac), (0x0001, 0x42), "LDA # {:?}", cpu.mode);
+	}
+}
+
+#[test]
+fn jsr_and_rts_wrap_past_ffff() {
+	// JSR at $FFFD pushes $FFFF, the address of its last byte
+	for (cpu, ram) in run_at(0xFFFD, &[0x20, 0x00, 0x03], |_, _| ()) {
+		assert_eq!(cpu.pc, 0x0300, "JSR {:?}", cpu.mode);
+		assert_eq!((ram.mem[0x01FF], ram.mem[0x01FE]), (0xFF, 0xFF), "JSR {:?}", cpu.mode);
+	}
+
+	// RTS to $FFFF + 1
+	let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+		cpu.sp = 0xFD;
+		ram.mem[0x01FE] = 0xFF;
+		ram.mem[0x01FF] = 0xFF;
+	};
+	for (cpu, _) in run_at(0x0400, &[0x60], setup) {
+		assert_eq!(cpu.pc, 0x0000, "RTS {:?}", cpu.mode);
+	}
+}
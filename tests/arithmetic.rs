@@ -0,0 +1,116 @@
+// ADC and SBC results and flags, binary and decimal. Every case runs in
+// both cores.
+
+
+mod common;
+
+use common::each_mode;
+use nes::bus::FlatRam;
+use nes::cpu::{Cpu, Status, StepMode, Variant};
+
+// Runs opcode #value on variant with A and P set
+fn execute(variant: Variant, mode: StepMode, sr: Status, opcode: u8, a: u8, value: u8) -> Cpu {
+	let mut ram = FlatRam::init();
+	ram.mem[0x0400] = opcode;
+	ram.mem[0x0401] = value;
+
+	let mut cpu = Cpu::with_variant(variant);
+	cpu.mode = mode;
+	cpu.pc = 0x0400;
+	cpu.ac = a;
+	cpu.sr = sr;
+	cpu.step_instruction(&mut ram);
+
+	cpu
+}
+
+// Runs opcode #value with A and C set, returning A, C and V after it
+fn run(mode: StepMode, opcode: u8, a: u8, value: u8, carry: bool) -> (u8, bool, bool) {
+	let mut sr = Status::U;
+	sr.set(Status::C, carry);
+	let cpu = execute(Variant::default(), mode, sr, opcode, a, value);
+
+	(cpu.ac, cpu.sr.contains(Status::C), cpu.sr.contains(Status::V))
+}
+
+#[test]
+fn overflow() {
+	// V is set when both inputs share a sign the result does not have
+	for (opcode, a, value, carry, expected) in [
+		// ADC
+		(0x69, 0x50, 0x10, false, (0x60, false, false)),
+		(0x69, 0x50, 0x50, false, (0xA0, false, true)),
+		(0x69, 0x50, 0x90, false, (0xE0, false, false)),
+		(0x69, 0xD0, 0x90, false, (0x60, true, true)),
+		(0x69, 0x7F, 0x00, true, (0x80, false, true)),
+		(0x69, 0xFF, 0x01, false, (0x00, true, false)),
+		// SBC
+		(0xE9, 0x50, 0xF0, true, (0x60, false, false)),
+		(0xE9, 0x50, 0xB0, true, (0xA0, false, true)),
+		(0xE9, 0xD0, 0x70, true, (0x60, true, true)),
+		(0xE9, 0x80, 0x00, false, (0x7F, true, true)),
+	] {
+		each_mode(|mode| {
+			assert_eq!(run(mode, opcode, a, value, carry), expected, "{:?} {:02X} A=${:02X} #${:02X} C={}", mode, opcode, a, value, carry);
+		});
+	}
+}
+
+
+//
+// Decimal Mode
+//
+// The NMOS 6502 adds and subtracts in BCD while D is set. ADC takes N and V
+// from the result before the high digit is corrected and Z from the binary
+// sum; SBC sets every flag as the binary subtraction would. The 2A03 has
+// the decimal circuitry disconnected and ignores D.
+
+// Runs opcode #value with D set, returning A and N, V, Z, C after it
+fn decimal(variant: Variant, mode: StepMode, opcode: u8, a: u8, value: u8, carry: bool) -> (u8, [bool; 4]) {
+	let mut sr = Status::U | Status::D;
+	sr.set(Status::C, carry);
+	let cpu = execute(variant, mode, sr, opcode, a, value);
+
+	let flags = [Status::N, Status::V, Status::Z, Status::C].map(|flag| cpu.sr.contains(flag));
+	(cpu.ac, flags)
+}
+
+#[test]
+fn nmos_decimal() {
+	// opcode, A, operand, C -> A, [N, V, Z, C]
+	for (opcode, a, value, carry, expected) in [
+		// ADC
+		(0x69, 0x12, 0x34, false, (0x46, [false, false, false, false])),
+		(0x69, 0x58, 0x46, true, (0x05, [true, true, false, true])),
+		(0x69, 0x99, 0x01, false, (0x00, [true, false, false, true])),	// Z from $9A, N from $A0
+		(0x69, 0x50, 0x50, false, (0x00, [true, true, false, true])),	// V as if $50+$50 were binary
+		(0x69, 0x00, 0x00, false, (0x00, [false, false, true, false])),
+		// SBC
+		(0xE9, 0x46, 0x12, true, (0x34, [false, false, false, true])),
+		(0xE9, 0x40, 0x13, true, (0x27, [false, false, false, true])),
+		(0xE9, 0x32, 0x02, false, (0x29, [false, false, false, true])),
+		(0xE9, 0x00, 0x01, true, (0x99, [true, false, false, false])),	// borrow out
+		(0xE9, 0x21, 0x21, true, (0x00, [false, false, true, true])),
+	] {
+		each_mode(|mode| {
+			let found = decimal(Variant::Nmos6502, mode, opcode, a, value, carry);
+			assert_eq!(found, expected, "{:?} {:02X} A=${:02X} #${:02X} C={}", mode, opcode, a, value, carry);
+		});
+	}
+}
+
+#[test]
+fn ricoh_ignores_decimal() {
+	// $09 + $01 and $10 - $01
+	for (variant, opcode, a, carry, result) in [
+		(Variant::Nmos6502, 0x69, 0x09, false, 0x10),
+		(Variant::Ricoh2A03, 0x69, 0x09, false, 0x0A),
+		(Variant::Nmos6502, 0xE9, 0x10, true, 0x09),
+		(Variant::Ricoh2A03, 0xE9, 0x10, true, 0x0F),
+	] {
+		each_mode(|mode| {
+			let (found, _) = decimal(variant, mode, opcode, a, 0x01, carry);
+			assert_eq!(found, result, "{:?} {:?} {:02X}", variant, mode, opcode);
+		});
+	}
+}
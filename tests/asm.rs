@@ -0,0 +1,143 @@
+// Assembler: expressions, addressing mode selection, .org chunks, errors,
+// and a round trip through the disassembler
+
+
+use nes::asm::assemble;
+use nes::cpu::LOOK_UP;
+use nes::disasm::disassemble;
+
+fn bytes(source: &str) -> Vec<u8> {
+	assemble(source).unwrap().bytes()
+}
+
+// Line and message of the error source fails with
+fn error(source: &str) -> (usize, String) {
+	match assemble(source) {
+		Ok(_) => panic!("assembled without error:{}", source),
+		Err(err) => (err.line, err.message),
+	}
+}
+
+#[test]
+fn expressions() {
+	assert_eq!(bytes(".byte 1+2*3, (1+2)*3, 7/2, 10%4, 1<<4, $F0>>4"), [7, 9, 3, 2, 16, 15]);
+	assert_eq!(bytes(".byte $10|%11, $1F&$F0, $FF^$0F, ~0&$FF, -1"), [0x13, 0x10, 0xF0, 0xFF, 0xFF]);
+	assert_eq!(bytes(".byte 'A', \"hi\", 0x7F, 12"), [0x41, b'h', b'i', 0x7F, 12]);
+	assert_eq!(bytes(".byte <$1234, >$1234"), [0x34, 0x12]);
+
+	// * is the address of the statement
+	assert_eq!(bytes(".org $0400\n.word *, *+2"), [0x00, 0x04, 0x02, 0x04]);
+
+	// Constants, including ones defined after their use
+	assert_eq!(bytes("N = 5\nLDA #N*2\nLDX #M\nM = N+1"), [0xA9, 10, 0xA2, 6]);
+}
+
+#[test]
+fn zero_page_when_known() {
+	let source = "
+		.org $0400
+	zp = $20
+		LDA zp
+		LDA zp,X
+		LDA $0020
+		LDA later
+		LDA later,X
+		STX later,Y
+	later = $30
+	";
+
+	assert_eq!(bytes(source), [
+		0xA5, 0x20,
+		0xB5, 0x20,
+		0xA5, 0x20,
+		// Forward references take the absolute form...
+		0xAD, 0x30, 0x00,
+		0xBD, 0x30, 0x00,
+		// ...unless the instruction has none
+		0x96, 0x30,
+	]);
+}
+
+#[test]
+fn labels_and_branches() {
+	let source = "
+		.org $0400
+	start:
+		LDX #$08
+	loop: DEX
+		BNE loop
+		BEQ done
+		JMP start
+	done:
+		RTS
+	";
+	let program = assemble(source).unwrap();
+
+	assert_eq!(program.bytes(), [0xA2, 0x08, 0xCA, 0xD0, 0xFD, 0xF0, 0x03, 0x4C, 0x00, 0x04, 0x60]);
+	assert_eq!(program.symbols["loop"], 0x0402);
+	assert_eq!(program.symbols["done"], 0x040A);
+	assert_eq!(program.labels()[&0x040A], "done");
+}
+
+#[test]
+fn unofficial_mnemonics_and_aliases() {
+	assert_eq!(bytes("LAX $20\nISB $20\nISC $20\nKIL"), [0xA7, 0x20, 0xE7, 0x20, 0xE7, 0x20, 0x02]);
+
+	// The official encoding wins where both exist
+	assert_eq!(bytes("SBC #$01\nNOP"), [0xE9, 0x01, 0xEA]);
+}
+
+#[test]
+fn org_starts_a_chunk() {
+	let program = assemble("
+		NOP
+		.org $0400
+		.org $8000
+		LDA #$01
+		.org $FFFC
+		.word $8000
+	").unwrap();
+
+	// Code before any .org is at $0000; an .org with nothing after it leaves no chunk
+	let chunks: Vec<(u16, Vec<u8>)> = program.chunks.iter().map(|c| (c.addr, c.bytes.clone())).collect();
+	assert_eq!(chunks, [(0x0000, vec![0xEA]), (0x8000, vec![0xA9, 0x01]), (0xFFFC, vec![0x00, 0x80])]);
+	assert_eq!(program.origin(), 0x0000);
+}
+
+#[test]
+fn errors() {
+	for (source, line, message) in [
+		("NOP\nLDA missing", 2, "undefined symbol 'missing'"),
+		(".org $0400\nloop:\n.byte 0\n.org $0500\nBNE loop", 5, "branch target out of range"),
+		("STA #$10", 1, "STA has no Immediate addressing mode"),
+		("LDA ($20", 1, "missing ')'"),
+		("FOO $20", 1, "unknown instruction 'FOO'"),
+		("a:\na:", 2, "'a' defined twice"),
+		(".byte 300", 1, "does not fit in a byte"),
+		(".org label\nlabel:", 1, ".org needs an address known in advance"),
+		(".fill 3", 1, "unknown directive '.fill'"),
+		(".org $FFFF\nLDA $1234", 2, "program runs past $FFFF"),
+		("LDA #$1G", 1, "bad number"),
+	] {
+		let (found_line, found_message) = error(source);
+		assert_eq!(found_line, line, "{:?}: {}", source, found_message);
+		assert!(found_message.contains(message), "{:?}: {}", source, found_message);
+	}
+}
+
+#[test]
+fn disassembly_round_trip() {
+	// Every official opcode, with operand bytes $34 $12, reassembles from
+	// its disassembly into the same bytes
+	for (opcode, op) in LOOK_UP.iter().enumerate() {
+		if !op.official {
+			continue;
+		}
+
+		let original = [opcode as u8, 0x34, 0x12];
+		let line = &disassemble(|a| original[a as usize - 0x0400], 0x0400, 0x0402)[0];
+		let source = format!(".org $0400\n{}", line.text(None));
+
+		assert_eq!(bytes(&source), original[..op.bytes as usize], "{:02X} {}", opcode, source);
+	}
+}
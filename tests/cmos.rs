@@ -0,0 +1,270 @@
+// 65C02 instructions and addressing modes
+//	http://www.6502.org/tutorials/65c02opcodes.html
+//
+// The assembler only knows the NMOS opcodes, so programs here are raw
+// bytes. Every case runs in both cores; the cycle-accurate one hands the
+// 65C02 to the instruction-level core, which must still see memory.
+
+
+mod common;
+
+use common::{each_mode, run};
+use nes::bus::FlatRam;
+use nes::cpu::{Cpu, CpuState, Interrupt, IrqSource, Status, StepMode, Variant};
+
+// 65C02 about to run bytes at $0400
+fn boot(mode: StepMode, bytes: &[u8]) -> (Cpu, FlatRam) {
+	let mut ram = FlatRam::init();
+	ram.mem[0x0400..0x0400 + bytes.len()].copy_from_slice(bytes);
+
+	let mut cpu = Cpu::with_variant(Variant::Wdc65C02);
+	cpu.mode = mode;
+	cpu.pc = 0x0400;
+
+	(cpu, ram)
+}
+
+// Runs instructions of bytes after setup, then checks the result
+fn check(bytes: &[u8], instructions: usize, setup: impl Fn(&mut Cpu, &mut FlatRam), check: impl Fn(StepMode, &Cpu, &FlatRam)) {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, bytes);
+		setup(&mut cpu, &mut ram);
+
+		run(&mut cpu, &mut ram, instructions);
+		check(mode, &cpu, &ram);
+	});
+}
+
+#[test]
+fn loads_and_stores() {
+	let program = [
+		0xA5, 0x10,	// LDA $10
+		0x69, 0x01,	// ADC #$01
+		0x85, 0x11,	// STA $11
+		0xE6, 0x11,	// INC $11
+		0xB2, 0x20,	// LDA ($20)
+	];
+	let setup = |_: &mut Cpu, ram: &mut FlatRam| {
+		ram.mem[0x10] = 0x42;
+		ram.mem[0x20] = 0x00;
+		ram.mem[0x21] = 0x03;
+		ram.mem[0x0300] = 0x99;
+	};
+
+	// $42 + 1 stored, then incremented in memory
+	check(&program, 5, setup, |mode, cpu, ram| {
+		assert_eq!(ram.mem[0x11], 0x44, "{:?}", mode);
+		assert_eq!(cpu.ac, 0x99, "{:?}", mode);
+	});
+}
+
+
+//
+// Bit Instructions
+
+#[test]
+fn rmb_and_smb() {
+	for bit in 0..8u8 {
+		let rmb = 0x07 | bit << 4;
+		check(&[rmb, 0x10], 1, |_, ram| ram.mem[0x10] = 0xFF, |mode, _, ram| {
+			assert_eq!(ram.mem[0x10], !(1 << bit), "RMB{} {:?}", bit, mode);
+		});
+
+		let smb = 0x87 | bit << 4;
+		check(&[smb, 0x10], 1, |_, ram| ram.mem[0x10] = 0x00, |mode, _, ram| {
+			assert_eq!(ram.mem[0x10], 1 << bit, "SMB{} {:?}", bit, mode);
+		});
+	}
+}
+
+#[test]
+fn bbr_and_bbs() {
+	// Taken to $0413, past the 3 byte instruction, or on to $0403
+	for bit in 0..8u8 {
+		for (value, set) in [(1 << bit, true), (!(1 << bit), false)] {
+			let bbr = 0x0F | bit << 4;
+			let bbs = 0x8F | bit << 4;
+
+			for (opcode, taken) in [(bbr, !set), (bbs, set)] {
+				check(&[opcode, 0x10, 0x10], 1, |_, ram| ram.mem[0x10] = value, |mode, cpu, _| {
+					let target = if taken { 0x0413 } else { 0x0403 };
+					assert_eq!(cpu.pc, target, "{:02X} ${:02X} {:?}", opcode, value, mode);
+				});
+			}
+		}
+	}
+}
+
+#[test]
+fn tsb_and_trb() {
+	// Z tells whether A and memory had bits in common beforehand
+	let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+		cpu.ac = 0x0F;
+		ram.mem[0x10] = 0xF0;
+		ram.mem[0x0300] = 0x3C;
+	};
+	check(&[0x04, 0x10], 1, setup, |mode, cpu, ram| {
+		assert_eq!((ram.mem[0x10], cpu.ac), (0xFF, 0x0F), "TSB {:?}", mode);
+		assert!(cpu.sr.contains(Status::Z), "TSB {:?}", mode);
+	});
+	check(&[0x1C, 0x00, 0x03], 1, setup, |mode, cpu, ram| {
+		assert_eq!((ram.mem[0x0300], cpu.ac), (0x30, 0x0F), "TRB {:?}", mode);
+		assert!(!cpu.sr.contains(Status::Z), "TRB {:?}", mode);
+	});
+}
+
+
+//
+// Stores, Stack and Branches
+
+#[test]
+fn stz() {
+	for (bytes, addr) in [
+		(&[0x64, 0x10][..], 0x0010),
+		(&[0x74, 0x10], 0x0012),
+		(&[0x9C, 0x00, 0x03], 0x0300),
+		(&[0x9E, 0x00, 0x03], 0x0302),
+	] {
+		let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+			cpu.ac = 0x55;
+			cpu.x = 0x02;
+			ram.mem[addr] = 0xAA;
+		};
+		check(bytes, 1, setup, |mode, _, ram| assert_eq!(ram.mem[addr], 0x00, "{:02X} {:?}", bytes[0], mode));
+	}
+}
+
+#[test]
+fn push_and_pull_x_and_y() {
+	// PHX, PHY, PLX, PLY swaps X and Y through the stack
+	let setup = |cpu: &mut Cpu, _: &mut FlatRam| {
+		cpu.sp = 0xFF;
+		cpu.x = 0x11;
+		cpu.y = 0x80;
+	};
+	check(&[0xDA, 0x5A], 2, setup, |mode, cpu, ram| {
+		assert_eq!((ram.mem[0x01FF], ram.mem[0x01FE], cpu.sp), (0x11, 0x80, 0xFD), "{:?}", mode);
+	});
+	check(&[0xDA, 0x5A, 0xFA], 3, setup, |mode, cpu, _| {
+		assert_eq!(cpu.x, 0x80, "PLX {:?}", mode);
+		assert!(cpu.sr.contains(Status::N), "PLX {:?}", mode);
+	});
+	check(&[0xDA, 0x5A, 0xFA, 0x7A], 4, setup, |mode, cpu, _| {
+		assert_eq!((cpu.x, cpu.y, cpu.sp), (0x80, 0x11, 0xFF), "PLY {:?}", mode);
+		assert!(!cpu.sr.contains(Status::N), "PLY {:?}", mode);
+	});
+}
+
+#[test]
+fn bra() {
+	for (offset, target) in [(0x10, 0x0412), (0xF0, 0x03F2)] {
+		check(&[0x80, offset], 1, |_, _| (), |mode, cpu, _| assert_eq!(cpu.pc, target, "{:?}", mode));
+	}
+}
+
+
+//
+// Addressing Modes
+
+#[test]
+fn zero_page_indirect() {
+	// ($20) points at $0300
+	let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+		cpu.ac = 0x21;
+		cpu.y = 0xFF;
+		ram.mem[0x20] = 0x00;
+		ram.mem[0x21] = 0x03;
+		ram.mem[0x0300] = 0x12;
+	};
+	check(&[0x72, 0x20], 1, setup, |mode, cpu, _| assert_eq!(cpu.ac, 0x33, "ADC {:?}", mode));
+	check(&[0x92, 0x20], 1, setup, |mode, _, ram| assert_eq!(ram.mem[0x0300], 0x21, "STA {:?}", mode));
+}
+
+#[test]
+fn jmp_absolute_indexed_indirect() {
+	let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+		cpu.x = 0x04;
+		ram.mem[0x0304] = 0x34;
+		ram.mem[0x0305] = 0x12;
+	};
+	check(&[0x7C, 0x00, 0x03], 1, setup, |mode, cpu, _| assert_eq!(cpu.pc, 0x1234, "{:?}", mode));
+
+	// The pointer is read across the page boundary
+	let setup = |_: &mut Cpu, ram: &mut FlatRam| {
+		ram.mem[0x02FF] = 0x78;
+		ram.mem[0x0300] = 0x56;
+	};
+	check(&[0x7C, 0xFF, 0x02], 1, setup, |mode, cpu, _| assert_eq!(cpu.pc, 0x5678, "{:?}", mode));
+}
+
+
+//
+// Decimal Mode
+
+#[test]
+fn decimal_flags_are_valid() {
+	// Unlike the NMOS chip, N and Z follow the BCD result
+	// opcode, A, operand, C -> A, [N, V, Z, C]
+	for (opcode, a, value, carry, expected) in [
+		(0x69, 0x99, 0x01, false, (0x00, [false, false, true, true])),
+		(0x69, 0x50, 0x50, false, (0x00, [false, true, true, true])),
+		(0x69, 0x45, 0x45, false, (0x90, [true, true, false, false])),
+		(0xE9, 0x00, 0x01, true, (0x99, [true, false, false, false])),
+		(0xE9, 0x21, 0x21, true, (0x00, [false, false, true, true])),
+		(0xE9, 0x90, 0x10, true, (0x80, [true, false, false, true])),
+	] {
+		let setup = |cpu: &mut Cpu, _: &mut FlatRam| {
+			cpu.ac = a;
+			cpu.sr = Status::U | Status::D;
+			cpu.sr.set(Status::C, carry);
+		};
+		check(&[opcode, value], 1, setup, |mode, cpu, _| {
+			let flags = [Status::N, Status::V, Status::Z, Status::C].map(|flag| cpu.sr.contains(flag));
+			assert_eq!((cpu.ac, flags), expected, "{:02X} A=${:02X} #${:02X} C={} {:?}", opcode, a, value, carry, mode);
+		});
+	}
+}
+
+
+//
+// STP and WAI
+
+#[test]
+fn stp_halts_until_reset() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, &[0xDB, 0xEA]);
+		ram.mem[0xFFFC] = 0x00;
+		ram.mem[0xFFFD] = 0x05;
+
+		cpu.step_instruction(&mut ram);
+		assert_eq!(cpu.state, CpuState::Halted { pc: 0x0400, opcode: 0xDB }, "{:?}", mode);
+
+		// Not even an NMI gets it going again
+		cpu.set_nmi(true);
+		assert_eq!(cpu.step_instruction(&mut ram).cycles, 0, "{:?}", mode);
+
+		cpu.reset(&mut ram);
+		assert_eq!(cpu.step_instruction(&mut ram).pc, 0x0500, "{:?}", mode);
+	});
+}
+
+#[test]
+fn wai_waits_for_an_interrupt() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, &[0xCB, 0xEA]);
+		cpu.sp = 0xFF;
+		ram.mem[0xFFFE] = 0x00;
+		ram.mem[0xFFFF] = 0x07;
+
+		cpu.step_instruction(&mut ram);
+		assert_eq!(cpu.run_cycles(&mut ram, 50), CpuState::Waiting, "{:?}", mode);
+		assert_eq!(cpu.pc, 0x0401, "{:?}", mode);
+
+		// With I clear the IRQ is taken, returning past the WAI
+		cpu.set_irq(IrqSource::External, true);
+		let step = cpu.step_instruction(&mut ram);
+		assert_eq!(step.interrupt, Some(Interrupt::Irq), "{:?}", mode);
+		assert_eq!(cpu.pc, 0x0700, "{:?}", mode);
+		assert_eq!((ram.mem[0x01FF], ram.mem[0x01FE]), (0x04, 0x01), "{:?}", mode);
+	});
+}
@@ -0,0 +1,107 @@
+// Helpers shared by the integration tests
+
+
+use std::path::PathBuf;
+
+use nes::asm::assemble;
+use nes::bus::{Bus, FlatRam};
+use nes::cpu::{Cpu, StepMode, Variant};
+
+// Test data that cannot live in the repository is looked up in tests/roms,
+// or in the directory named by NES_TEST_ROMS
+pub fn roms_dir() -> PathBuf {
+	std::env::var_os("NES_TEST_ROMS")
+		.map(PathBuf::from)
+		.unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/roms"))
+}
+
+// Contents of a file in roms_dir. The tests that need these files are
+// #[ignore]d, so a missing one fails rather than passing untested.
+#[allow(dead_code)]
+pub fn rom(name: &str) -> Vec<u8> {
+	let path = roms_dir().join(name);
+
+	std::fs::read(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+}
+
+
+//
+// Running programs
+
+// Assembles source into a FlatRam and points a new Cpu at its start label,
+// or at its first byte when it has none
+#[allow(dead_code)]
+pub fn boot_variant(variant: Variant, mode: StepMode, source: &str) -> (Cpu, FlatRam) {
+	let program = assemble(source).unwrap();
+
+	let mut ram = FlatRam::init();
+	program.load(&mut ram);
+
+	let mut cpu = Cpu::with_variant(variant);
+	cpu.mode = mode;
+	cpu.pc = program.symbols.get("start").copied().unwrap_or(program.origin());
+
+	(cpu, ram)
+}
+
+#[allow(dead_code)]
+pub fn boot(mode: StepMode, source: &str) -> (Cpu, FlatRam) {
+	boot_variant(Variant::default(), mode, source)
+}
+
+#[allow(dead_code)]
+pub fn run(cpu: &mut Cpu, ram: &mut FlatRam, instructions: usize) {
+	for _ in 0..instructions {
+		cpu.step_instruction(ram);
+	}
+}
+
+// Runs test once with each core
+#[allow(dead_code)]
+pub fn each_mode(test: impl Fn(StepMode)) {
+	for mode in [StepMode::Instruction, StepMode::Cycle] {
+		test(mode);
+	}
+}
+
+
+//
+// Recording Bus
+
+// Flat memory that records every access
+#[allow(dead_code)]
+pub struct Recorder {
+	pub mem: Vec<u8>,
+	pub log: Vec<(u16, u8, &'static str)>,
+}
+
+#[allow(dead_code)]
+impl Recorder {
+
+	pub fn init() -> Self {
+		Self {
+			mem: vec![0; 64*1024],
+			log: Vec::new(),
+		}
+	}
+
+}
+
+impl Bus for Recorder {
+
+	fn read(&mut self, addr: u16) -> u8 {
+		let data = self.mem[addr as usize];
+		self.log.push((addr, data, "read"));
+		data
+	}
+
+	fn write(&mut self, addr: u16, data: u8) {
+		self.mem[addr as usize] = data;
+		self.log.push((addr, data, "write"));
+	}
+
+	fn peek(&self, addr: u16) -> u8 {
+		self.mem[addr as usize]
+	}
+
+}
@@ -0,0 +1,131 @@
+// CPU test ROMs
+//
+// The ROMs are not part of the repository, so these tests are ignored by
+// default. Put the files in tests/roms (or the directory named by
+// NES_TEST_ROMS) and run cargo test -- --ignored; a missing file fails.
+//
+//	nestest.nes, nestest.log	https://www.qmtpro.com/~nes/misc/
+//	6502_functional_test.bin	https://github.com/Klaus2m5/6502_65C02_functional_tests
+//	6502_decimal_test.bin		(same repository, assembled at $0200)
+
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use common::rom;
+use nes::bus::FlatRam;
+use nes::cartridge;
+use nes::cpu::{Cpu, CpuState, Variant};
+use nes::nes::Nes;
+
+
+//
+// nestest
+
+// Splits a log line into the parts that must match: address, bytes and
+// instruction up to any memory annotation, then registers, PPU position and
+// CYC. Annotation values are left out.
+fn nestest_fields(line: &str) -> (String, String) {
+	let instruction = line.get(..48).unwrap_or(line);
+	let instruction = instruction.split(" @ ").next().unwrap().split(" = ").next().unwrap().trim_end();
+
+	let registers = line.get(48..).unwrap_or("");
+
+	(instruction.to_string(), registers.to_string())
+}
+
+#[test]
+#[ignore = "needs nestest.nes and nestest.log"]
+fn nestest() {
+	let (rom, log) = (rom("nestest.nes"), rom("nestest.log"));
+	let golden = String::from_utf8_lossy(&log);
+
+	let mut nes = Nes::init();
+	nes.insert_cartridge(cartridge::load_ines(&rom).unwrap());
+	nes.power_on();
+
+	// Automation mode runs every test without a PPU and returns with RTS
+	nes.cpu.pc = 0xC000;
+
+	let lines = Rc::new(RefCell::new(Vec::<String>::new()));
+	let sink = lines.clone();
+	nes.cpu.trace = Some(Box::new(move |line: &str| sink.borrow_mut().push(line.to_string())));
+
+	let mut previous = String::new();
+	for (n, expected) in golden.lines().enumerate() {
+		nes.step_instruction();
+		let actual = lines.borrow_mut().pop().unwrap_or_default();
+
+		assert_eq!(
+			nestest_fields(&actual), nestest_fields(expected),
+			"nestest.log line {} differs\n  after: {}\n  ours:  {}\n  log:   {}",
+			n + 1, previous, actual, expected,
+		);
+		previous = actual;
+	}
+
+	// Official and unofficial opcode results
+	let official = nes.bus.ram[0x02];
+	let unofficial = nes.bus.ram[0x03];
+	assert_eq!((official, unofficial), (0, 0), "nestest error codes ${:02X} ${:02X}", official, unofficial);
+}
+
+
+//
+// Klaus Dormann
+
+// Runs until the program jumps to itself, returning where it stopped
+fn run_to_trap(cpu: &mut Cpu, ram: &mut FlatRam, max_instructions: u64) -> u16 {
+	for _ in 0..max_instructions {
+		let pc = cpu.pc;
+		cpu.step_instruction(ram);
+
+		if let CpuState::Halted { pc, .. } = cpu.state {
+			return pc;
+		}
+		if cpu.pc == pc {
+			return pc;
+		}
+	}
+
+	panic!("no trap after {} instructions, pc ${:04X}", max_instructions, cpu.pc);
+}
+
+fn klaus_cpu(image: &[u8], load: u16, start: u16) -> (Cpu, FlatRam) {
+	let mut ram = FlatRam::init();
+	let load = if image.len() == 64*1024 { 0 } else { load as usize };
+	ram.mem[load..load + image.len()].copy_from_slice(image);
+
+	// Both programs check BCD arithmetic, so run them as a plain NMOS 6502
+	let mut cpu = Cpu::with_variant(Variant::Nmos6502);
+	cpu.power_on(&mut ram);
+	cpu.pc = start;
+
+	(cpu, ram)
+}
+
+#[test]
+#[ignore = "needs 6502_functional_test.bin"]
+fn klaus_functional() {
+	let image = rom("6502_functional_test.bin");
+	let (mut cpu, mut ram) = klaus_cpu(&image, 0x0000, 0x0400);
+
+	let trap = run_to_trap(&mut cpu, &mut ram, 100_000_000);
+
+	// test_case lives at $0200 in the stock build
+	assert_eq!(trap, 0x3469, "trapped at ${:04X} in test ${:02X}", trap, ram.mem[0x0200]);
+}
+
+#[test]
+#[ignore = "needs 6502_decimal_test.bin"]
+fn klaus_decimal() {
+	let image = rom("6502_decimal_test.bin");
+	let (mut cpu, mut ram) = klaus_cpu(&image, 0x0200, 0x0200);
+
+	let trap = run_to_trap(&mut cpu, &mut ram, 100_000_000);
+
+	// ERROR is cleared only when every combination checked out
+	assert_eq!(ram.mem[0x000B], 0, "decimal test failed, trapped at ${:04X}", trap);
+}
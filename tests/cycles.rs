@@ -0,0 +1,241 @@
+// Instruction timing against the published tables
+//	https://www.nesdev.org/wiki/CPU_unofficial_opcodes
+//	http://www.6502.org/tutorials/6502opcodes.html
+//
+// Every opcode runs once with indexing that stays on the page and once with
+// indexing that crosses it; branches also run not taken, taken and taken
+// across a page. Both cores must match the tables. A few instructions also
+// have their bus accesses checked cycle by cycle.
+
+
+mod common;
+
+use common::Recorder;
+use nes::bus::FlatRam;
+use nes::cpu::{Cpu, Status, StepMode, Variant, LOOK_UP};
+
+// Base cycles, 0 for JAM
+const CYCLES: [u8; 256] = [
+//	0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F
+	7, 6, 0, 8, 3, 3, 5, 5, 3, 2, 2, 2, 4, 4, 6, 6,	// 0
+	2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,	// 1
+	6, 6, 0, 8, 3, 3, 5, 5, 4, 2, 2, 2, 4, 4, 6, 6,	// 2
+	2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,	// 3
+	6, 6, 0, 8, 3, 3, 5, 5, 3, 2, 2, 2, 3, 4, 6, 6,	// 4
+	2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,	// 5
+	6, 6, 0, 8, 3, 3, 5, 5, 4, 2, 2, 2, 5, 4, 6, 6,	// 6
+	2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,	// 7
+	2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4,	// 8
+	2, 6, 0, 6, 4, 4, 4, 4, 2, 5, 2, 5, 5, 5, 5, 5,	// 9
+	2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4,	// A
+	2, 5, 0, 5, 4, 4, 4, 4, 2, 4, 2, 4, 4, 4, 4, 4,	// B
+	2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,	// C
+	2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,	// D
+	2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,	// E
+	2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,	// F
+];
+
+// One more cycle when indexing crosses a page. Stores and read-modify-write
+// instructions always take the long path, so they are in CYCLES instead.
+const PAGE_PENALTY: [u8; 256] = [
+//	0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// 0
+	0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0,	// 1
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// 2
+	0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0,	// 3
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// 4
+	0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0,	// 5
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// 6
+	0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0,	// 7
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// 8
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// 9
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// A
+	0, 1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 1, 1, 1, 1,	// B
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// C
+	0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0,	// D
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,	// E
+	0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0,	// F
+];
+
+const START: u16 = 0x0400;
+
+// Runs one instruction from START and returns the cycles it took. Every zero
+// page byte is $02, so any pointer leads to $0202; absolute operands are
+// $0210. With index 0 nothing crosses a page, with $FF everything does.
+fn time(variant: Variant, mode: StepMode, bytes: &[u8], index: u8, sr: Status) -> u32 {
+	let mut ram = FlatRam::init();
+	ram.mem[..0x100].fill(0x02);
+	ram.mem[START as usize..START as usize + bytes.len()].copy_from_slice(bytes);
+
+	let mut cpu = Cpu::with_variant(variant);
+	cpu.mode = mode;
+	cpu.pc = START;
+	cpu.x = index;
+	cpu.y = index;
+	cpu.sr = sr;
+
+	cpu.step_instruction(&mut ram).cycles
+}
+
+// Flag state under which a branch opcode is (or is not) taken
+fn branch_flags(opcode: u8, taken: bool) -> Status {
+	let flag = [Status::N, Status::V, Status::C, Status::Z][(opcode >> 6) as usize];
+	let set = (opcode & 0x20 != 0) == taken;
+
+	let mut sr = Status::U | Status::I;
+	sr.set(flag, set);
+	sr
+}
+
+#[test]
+fn opcode_timing() {
+	let mut failures = Vec::new();
+
+	for mode in [StepMode::Instruction, StepMode::Cycle] {
+		for opcode in 0..=255u8 {
+			let base = CYCLES[opcode as usize] as u32;
+			if base == 0 {
+				continue;
+			}
+
+			let name = LOOK_UP[opcode as usize].mnemonic;
+			let mut check = |case: &str, actual: u32, expected: u32| {
+				if actual != expected {
+					failures.push(format!("{:?} {:02X} {} {}: {} cycles, expected {}", mode, opcode, name, case, actual, expected));
+				}
+			};
+
+			if opcode & 0x1F == 0x10 {
+				// Offsets from the next instruction at $0402: $0412 is on the
+				// same page, $03F2 is not
+				let sr = branch_flags(opcode, false);
+				check("not taken", time(Variant::Ricoh2A03, mode, &[opcode, 0x10], 0, sr), base);
+				let sr = branch_flags(opcode, true);
+				check("taken", time(Variant::Ricoh2A03, mode, &[opcode, 0x10], 0, sr), base + 1);
+				check("taken across a page", time(Variant::Ricoh2A03, mode, &[opcode, 0xF0], 0, sr), base + 2);
+				continue;
+			}
+
+			let bytes = [opcode, 0x10, 0x02];
+			let sr = Status::U | Status::I;
+			let penalty = PAGE_PENALTY[opcode as usize] as u32;
+			check("same page", time(Variant::Ricoh2A03, mode, &bytes, 0x00, sr), base);
+			check("page crossed", time(Variant::Ricoh2A03, mode, &bytes, 0xFF, sr), base + penalty);
+		}
+	}
+
+	if !failures.is_empty() {
+		panic!("{} timing errors:\n{}", failures.len(), failures.join("\n"));
+	}
+}
+
+#[test]
+fn cmos_timing() {
+	let sr = Status::U | Status::I;
+	let time = |bytes: &[u8], index: u8, sr: Status| time(Variant::Wdc65C02, StepMode::Instruction, bytes, index, sr);
+
+	// Shifts by abs,X skip the extra cycle unless indexing crosses a page;
+	// INC and DEC never do
+	for opcode in [0x1E, 0x3E, 0x5E, 0x7E] {
+		assert_eq!(time(&[opcode, 0x10, 0x02], 0x00, sr), 6, "{:02X}", opcode);
+		assert_eq!(time(&[opcode, 0x10, 0x02], 0xFF, sr), 7, "{:02X}", opcode);
+	}
+	for opcode in [0xDE, 0xFE] {
+		assert_eq!(time(&[opcode, 0x10, 0x02], 0x00, sr), 7, "{:02X}", opcode);
+		assert_eq!(time(&[opcode, 0x10, 0x02], 0xFF, sr), 7, "{:02X}", opcode);
+	}
+
+	// BRA is always taken
+	assert_eq!(time(&[0x80, 0x10], 0, sr), 3);
+	assert_eq!(time(&[0x80, 0xF0], 0, sr), 4);
+
+	// Decimal ADC and SBC take one more cycle
+	assert_eq!(time(&[0x69, 0x01], 0, sr), 2);
+	assert_eq!(time(&[0x69, 0x01], 0, sr | Status::D), 3);
+	assert_eq!(time(&[0xF9, 0x10, 0x02], 0xFF, sr | Status::D), 6);
+}
+
+
+//
+// Bus Accesses
+//
+// The cycle-accurate core makes the same reads and writes as the chip,
+// dummy ones included, one per cycle.
+
+// Bus log of one instruction at pc, with bytes placed there, from the
+// cycle-accurate core
+fn accesses(pc: u16, bytes: &[u8], setup: impl Fn(&mut Cpu, &mut Recorder)) -> Vec<(u16, u8, &'static str)> {
+	let mut bus = Recorder::init();
+	bus.mem[pc as usize..pc as usize + bytes.len()].copy_from_slice(bytes);
+
+	let mut cpu = Cpu::init();
+	cpu.mode = StepMode::Cycle;
+	cpu.pc = pc;
+	cpu.sp = 0xFD;
+	setup(&mut cpu, &mut bus);
+
+	let step = cpu.step_instruction(&mut bus);
+	assert_eq!(step.cycles as usize, bus.log.len(), "one access per cycle");
+	bus.log
+}
+
+#[test]
+fn indexed_accesses() {
+	// $0210 + $FF: the first read uses the low byte before the carry
+	// reaches the high byte
+	let crossing = |cpu: &mut Cpu, bus: &mut Recorder| {
+		cpu.x = 0xFF;
+		bus.mem[0x020F] = 0x99;
+		bus.mem[0x030F] = 0x41;
+	};
+	assert_eq!(accesses(0x0400, &[0xBD, 0x10, 0x02], crossing), [
+		(0x0400, 0xBD, "read"), (0x0401, 0x10, "read"), (0x0402, 0x02, "read"),
+		(0x020F, 0x99, "read"), (0x030F, 0x41, "read"),
+	]);
+
+	// Read-modify-write writes the old value back before the new one
+	assert_eq!(accesses(0x0400, &[0xFE, 0x10, 0x02], crossing), [
+		(0x0400, 0xFE, "read"), (0x0401, 0x10, "read"), (0x0402, 0x02, "read"),
+		(0x020F, 0x99, "read"), (0x030F, 0x41, "read"), (0x030F, 0x41, "write"), (0x030F, 0x42, "write"),
+	]);
+
+	// Stores read the target first even when nothing crosses a page
+	let store = |cpu: &mut Cpu, bus: &mut Recorder| {
+		cpu.x = 0x01;
+		cpu.ac = 0x5A;
+		bus.mem[0x0211] = 0x99;
+	};
+	assert_eq!(accesses(0x0400, &[0x9D, 0x10, 0x02], store), [
+		(0x0400, 0x9D, "read"), (0x0401, 0x10, "read"), (0x0402, 0x02, "read"),
+		(0x0211, 0x99, "read"), (0x0211, 0x5A, "write"),
+	]);
+}
+
+#[test]
+fn branch_accesses() {
+	// Taken from $04FF to $050F: the opcode after the branch is read, then
+	// $040F before PCH is fixed
+	let log = accesses(0x04FD, &[0xD0, 0x10, 0xEA], |_, bus| bus.mem[0x040F] = 0x77);
+	assert_eq!(log, [(0x04FD, 0xD0, "read"), (0x04FE, 0x10, "read"), (0x04FF, 0xEA, "read"), (0x040F, 0x77, "read")]);
+}
+
+#[test]
+fn subroutine_accesses() {
+	// JSR reads the stack before pushing, and the high address byte last
+	let log = accesses(0x0400, &[0x20, 0x00, 0x03], |_, _| ());
+	assert_eq!(log, [
+		(0x0400, 0x20, "read"), (0x0401, 0x00, "read"), (0x01FD, 0x00, "read"),
+		(0x01FD, 0x04, "write"), (0x01FC, 0x02, "write"), (0x0402, 0x03, "read"),
+	]);
+
+	// RTS pulls $0402, then reads there before stepping past it
+	let pushed = |cpu: &mut Cpu, bus: &mut Recorder| {
+		cpu.sp = 0xFB;
+		bus.mem[0x01FC] = 0x02;
+		bus.mem[0x01FD] = 0x04;
+	};
+	assert_eq!(accesses(0x0300, &[0x60], pushed), [
+		(0x0300, 0x60, "read"), (0x0301, 0x00, "read"), (0x01FB, 0x00, "read"),
+		(0x01FC, 0x02, "read"), (0x01FD, 0x04, "read"), (0x0402, 0x00, "read"),
+	]);
+}
@@ -0,0 +1,130 @@
+// Disassembler output: operand syntax for every addressing mode, branch
+// targets, labels, unofficial opcodes and ranges
+
+
+use nes::cpu::Variant;
+use nes::disasm::{decode, decode_for, disassemble, listing, Labels};
+
+// Text of the single instruction in bytes, placed at addr
+fn text_at(variant: Variant, addr: u16, bytes: &[u8]) -> String {
+	let mem = |a: u16| bytes[a.wrapping_sub(addr) as usize];
+	decode_for(variant, mem, addr).text(None)
+}
+
+fn text(bytes: &[u8]) -> String {
+	text_at(Variant::Ricoh2A03, 0x0400, bytes)
+}
+
+#[test]
+fn operand_syntax() {
+	for (bytes, expected) in [
+		(&[0xEA][..], "NOP"),
+		(&[0x0A], "ASL A"),
+		(&[0xA9, 0x20], "LDA #$20"),
+		(&[0xA5, 0x20], "LDA $20"),
+		(&[0xB5, 0x20], "LDA $20,X"),
+		(&[0xB6, 0x20], "LDX $20,Y"),
+		(&[0xAD, 0x34, 0x12], "LDA $1234"),
+		(&[0xBD, 0x34, 0x12], "LDA $1234,X"),
+		(&[0xB9, 0x34, 0x12], "LDA $1234,Y"),
+		(&[0x6C, 0x34, 0x12], "JMP ($1234)"),
+		(&[0xA1, 0x20], "LDA ($20,X)"),
+		(&[0xB1, 0x20], "LDA ($20),Y"),
+	] {
+		assert_eq!(text(bytes), expected, "{:02X?}", bytes);
+	}
+}
+
+#[test]
+fn cmos_operand_syntax() {
+	for (bytes, expected) in [
+		(&[0xB2, 0x20][..], "LDA ($20)"),
+		(&[0x7C, 0x34, 0x12], "JMP ($1234,X)"),
+		(&[0x0F, 0x20, 0xFD], "BBR0 $20,$0400"),
+		(&[0x80, 0x10], "BRA $0412"),
+	] {
+		assert_eq!(text_at(Variant::Wdc65C02, 0x0400, bytes), expected, "{:02X?}", bytes);
+	}
+}
+
+#[test]
+fn branch_targets() {
+	// Offsets count from the instruction that follows, and wrap around
+	for (addr, bytes, target) in [
+		(0xC000, [0xD0, 0xFE], 0xC000),
+		(0xC000, [0x10, 0x7F], 0xC081),
+		(0xC000, [0x30, 0x80], 0xBF82),
+		(0x0000, [0xF0, 0xFC], 0xFFFE),
+		(0xFFFE, [0x90, 0x02], 0x0002),
+	] {
+		let instruction = decode(|a| bytes[a.wrapping_sub(addr) as usize], addr);
+		assert_eq!(instruction.target, Some(target), "${:04X} {:02X?}", addr, bytes);
+		assert_eq!(instruction.operand_text(None), format!("${:04X}", target));
+	}
+}
+
+#[test]
+fn labels_replace_addresses() {
+	let labels: Labels = [(0xC000, "loop".to_string()), (0x0020, "ptr".to_string()), (0x8000, "init".to_string())].into();
+
+	let decoded = |addr: u16, bytes: &[u8]| {
+		let mem = |a: u16| bytes[a.wrapping_sub(addr) as usize];
+		decode(mem, addr).text(Some(&labels))
+	};
+	assert_eq!(decoded(0xC002, &[0xD0, 0xFC]), "BNE loop");
+	assert_eq!(decoded(0xC000, &[0x20, 0x00, 0x80]), "JSR init");
+	assert_eq!(decoded(0xC000, &[0xB1, 0x20]), "LDA (ptr),Y");
+
+	// Immediate values are never names
+	assert_eq!(decoded(0xC000, &[0xA9, 0x20]), "LDA #$20");
+}
+
+#[test]
+fn unofficial_opcodes_are_marked() {
+	let lax = decode(|a| [0xA7, 0x20][a as usize], 0x0000);
+	assert_eq!((lax.mnemonic, lax.official), ("LAX", false));
+
+	// Same mnemonic as the official opcode
+	let sbc = decode(|a| [0xEB, 0x20][a as usize], 0x0000);
+	assert_eq!((sbc.text(None).as_str(), sbc.official), ("SBC #$20", false));
+
+	let lda = decode(|a| [0xA5, 0x20][a as usize], 0x0000);
+	assert!(lda.official);
+}
+
+#[test]
+fn range_in_address_order() {
+	let rom = [0xA9, 0x20, 0x8D, 0x00, 0x20, 0xEA, 0xD0, 0xFB];
+	let lines = disassemble(|a| rom[a as usize - 0x8000], 0x8000, 0x8007);
+
+	let found: Vec<(u16, String)> = lines.iter().map(|i| (i.addr, i.text(None))).collect();
+	assert_eq!(found, [
+		(0x8000, "LDA #$20".to_string()),
+		(0x8002, "STA $2000".to_string()),
+		(0x8005, "NOP".to_string()),
+		(0x8006, "BNE $8003".to_string()),
+	]);
+	assert_eq!(lines[1].raw(), [0x8D, 0x00, 0x20]);
+	assert_eq!(lines[1].next(), 0x8005);
+}
+
+#[test]
+fn truncated_instruction_at_the_end() {
+	// The STA is missing its last byte; reading it would index past rom
+	let rom = [0xA9, 0x20, 0x8D, 0x00];
+	let lines = disassemble(|a| rom[a as usize], 0x0000, 0x0003);
+
+	assert_eq!(lines.len(), 1);
+	assert_eq!(lines[0].text(None), "LDA #$20");
+}
+
+#[test]
+fn listing_format() {
+	let rom = [0xA9, 0x20, 0xD0, 0xFC];
+	let labels: Labels = [(0x8000, "start".to_string())].into();
+
+	assert_eq!(
+		listing(|a| rom[a as usize - 0x8000], 0x8000, 0x8003, Some(&labels)),
+		"start:\n$8000: A9 20     LDA #$20\n$8002: D0 FC     BNE start\n",
+	);
+}
@@ -0,0 +1,320 @@
+// NMI and IRQ inputs
+//
+// NMI is edge triggered: the inactive -> active transition latches one
+// interrupt, however long the line is then held. IRQ is a level shared by
+// several sources and is serviced for as long as any of them holds it and
+// the I flag allows. Every case runs in both cores.
+
+
+mod common;
+
+use common::{boot, each_mode, run};
+use nes::bus::FlatRam;
+use nes::cpu::{Cpu, IrqSource, Status, StepMode};
+
+// Handlers count how often they run: NMIs in $10, IRQs in $11
+const NMI_HANDLER: u16 = 0x0600;
+const IRQ_HANDLER: u16 = 0x0700;
+const HANDLERS: &str = "
+	.org $0600
+nmi:
+	INC $10
+	RTI
+	.org $0700
+irq:
+	INC $11
+	RTI
+	.org $FFFA
+	.word nmi, start, irq
+";
+
+// Endless NOPs at $0400
+const NOPS: &str = "
+	.org $0400
+start:
+	NOP
+	NOP
+	NOP
+	NOP
+	JMP start
+";
+
+// source with the handlers and vectors above added
+fn boot_with_handlers(mode: StepMode, source: &str) -> (Cpu, FlatRam) {
+	boot(mode, &format!("{}{}", source, HANDLERS))
+}
+
+#[test]
+fn irq_is_masked_by_i() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_with_handlers(mode, NOPS);
+		cpu.sr.insert(Status::I);
+		cpu.set_irq(IrqSource::External, true);
+
+		run(&mut cpu, &mut ram, 50);
+		assert_eq!(ram.mem[0x11], 0, "{:?}", mode);
+
+		cpu.sr.remove(Status::I);
+		run(&mut cpu, &mut ram, 3);
+		assert_ne!(ram.mem[0x11], 0, "{:?}", mode);
+	});
+}
+
+#[test]
+fn nmi_fires_once_per_edge() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_with_handlers(mode, NOPS);
+
+		cpu.set_nmi(true);
+		run(&mut cpu, &mut ram, 50);
+		assert_eq!(ram.mem[0x10], 1, "{:?}", mode);
+
+		// Still active: no new edge
+		cpu.set_nmi(true);
+		run(&mut cpu, &mut ram, 50);
+		assert_eq!(ram.mem[0x10], 1, "{:?}", mode);
+
+		cpu.set_nmi(false);
+		cpu.set_nmi(true);
+		run(&mut cpu, &mut ram, 50);
+		assert_eq!(ram.mem[0x10], 2, "{:?}", mode);
+	});
+}
+
+#[test]
+fn nmi_is_not_masked_by_i() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_with_handlers(mode, NOPS);
+		cpu.sr.insert(Status::I);
+
+		cpu.set_nmi(true);
+		run(&mut cpu, &mut ram, 10);
+		assert_eq!(ram.mem[0x10], 1, "{:?}", mode);
+	});
+}
+
+#[test]
+fn irq_held_while_any_source_asserts() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_with_handlers(mode, NOPS);
+
+		cpu.set_irq(IrqSource::FrameCounter, true);
+		cpu.set_irq(IrqSource::Mapper, true);
+		run(&mut cpu, &mut ram, 20);
+		let count = ram.mem[0x11];
+		assert_ne!(count, 0, "{:?}", mode);
+
+		// The mapper still holds the line, so the handler keeps being entered
+		cpu.set_irq(IrqSource::FrameCounter, false);
+		assert!(cpu.irq_active());
+		run(&mut cpu, &mut ram, 20);
+		assert!(ram.mem[0x11] > count, "{:?}", mode);
+
+		cpu.set_irq(IrqSource::Mapper, false);
+		assert!(!cpu.irq_active());
+		run(&mut cpu, &mut ram, 5);
+		let count = ram.mem[0x11];
+		run(&mut cpu, &mut ram, 20);
+		assert_eq!(ram.mem[0x11], count, "{:?}", mode);
+	});
+}
+
+#[test]
+fn nmi_wins_over_irq() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_with_handlers(mode, NOPS);
+		cpu.set_irq(IrqSource::External, true);
+		cpu.set_nmi(true);
+
+		// The NMI sequence sets I, so its handler runs undisturbed
+		while cpu.pc != NMI_HANDLER && cpu.pc != IRQ_HANDLER {
+			cpu.step_instruction(&mut ram);
+		}
+		assert_eq!(cpu.pc, NMI_HANDLER, "{:?}", mode);
+	});
+}
+
+
+//
+// Timing
+//
+// Interrupts are polled before the last cycle of an instruction, and a
+// 7 cycle sequence then pushes PC and P and fetches the vector.
+
+// Cycles the CPU, calling at(cpu, clock) before each one, until it is
+// between instructions with PC at addr. Returns the clock then.
+fn run_to(cpu: &mut Cpu, ram: &mut FlatRam, addr: u16, mut at: impl FnMut(&mut Cpu, u128)) -> u128 {
+	for _ in 0..1000 {
+		if cpu.at_boundary() && cpu.pc == addr {
+			return cpu.global_clock;
+		}
+		at(cpu, cpu.global_clock);
+		cpu.cycle(ram);
+	}
+
+	panic!("never reached ${:04X}, pc ${:04X}", addr, cpu.pc);
+}
+
+// Return address and status pushed by the interrupt just taken
+fn stacked(cpu: &Cpu, ram: &FlatRam) -> (u16, Status) {
+	let sp = cpu.sp as usize;
+	let status = Status::from_bits(ram.mem[0x0100 + sp + 1]);
+	let pc = ram.mem[0x0100 + sp + 2] as u16 | (ram.mem[0x0100 + sp + 3] as u16) << 8;
+	(pc, status)
+}
+
+#[test]
+fn nmi_polled_before_last_cycle() {
+	// The first NOP takes cycles 1-2 and polls before cycle 2. An edge after
+	// cycle 1 is seen there, so the sequence runs on cycles 3-9; an edge
+	// after cycle 2 waits for the second NOP, moving it to 5-11.
+	for (edge, entered, returns_to) in [(1, 9, 0x0401), (2, 11, 0x0402)] {
+		each_mode(|mode| {
+			let (mut cpu, mut ram) = boot_with_handlers(mode, NOPS);
+			let clock = run_to(&mut cpu, &mut ram, NMI_HANDLER, |cpu, clock| {
+				if clock == edge {
+					cpu.set_nmi(true);
+				}
+			});
+
+			assert_eq!(clock, entered, "{:?}, edge after cycle {}", mode, edge);
+			let (pc, status) = stacked(&cpu, &ram);
+			assert_eq!(pc, returns_to, "{:?}", mode);
+			assert!(!status.contains(Status::B), "{:?}", mode);
+			assert!(status.contains(Status::U), "{:?}", mode);
+		});
+	}
+}
+
+#[test]
+fn cli_takes_effect_after_next_instruction() {
+	let source = "
+		.org $0400
+	start:
+		CLI
+		NOP
+		NOP
+		NOP
+	";
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_with_handlers(mode, source);
+		cpu.sr.insert(Status::I);
+		cpu.set_irq(IrqSource::External, true);
+
+		// CLI 1-2, NOP 3-4, sequence 5-11
+		let clock = run_to(&mut cpu, &mut ram, IRQ_HANDLER, |_, _| ());
+		assert_eq!(clock, 11, "{:?}", mode);
+		assert_eq!(stacked(&cpu, &ram).0, 0x0402, "{:?}", mode);
+	});
+}
+
+#[test]
+fn irq_taken_after_sei() {
+	let source = "
+		.org $0400
+	start:
+		SEI
+		NOP
+	";
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_with_handlers(mode, source);
+
+		// SEI's poll still sees I clear, so the IRQ follows it, pushing
+		// the I flag SEI has already set
+		let clock = run_to(&mut cpu, &mut ram, IRQ_HANDLER, |cpu, clock| {
+			if clock == 1 {
+				cpu.set_irq(IrqSource::External, true);
+			}
+		});
+		assert_eq!(clock, 9, "{:?}", mode);
+
+		let (pc, status) = stacked(&cpu, &ram);
+		assert_eq!(pc, 0x0401, "{:?}", mode);
+		assert!(status.contains(Status::I), "{:?}", mode);
+	});
+}
+
+#[test]
+fn plp_clearing_i_takes_effect_after_next_instruction() {
+	let source = "
+		.org $0400
+	start:
+		PLP
+		NOP
+		NOP
+	";
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_with_handlers(mode, source);
+		cpu.sr.insert(Status::I);
+		cpu.sp = 0xFE;
+		ram.mem[0x01FF] = Status::U.bits();
+		cpu.set_irq(IrqSource::External, true);
+
+		// PLP 1-4, NOP 5-6, sequence 7-13
+		let clock = run_to(&mut cpu, &mut ram, IRQ_HANDLER, |_, _| ());
+		assert_eq!(clock, 13, "{:?}", mode);
+		assert_eq!(stacked(&cpu, &ram).0, 0x0402, "{:?}", mode);
+	});
+}
+
+#[test]
+fn nmi_hijacks_brk() {
+	// BRK runs on cycles 1-7. An NMI edge up to cycle 4 takes over the
+	// vector fetch, leaving B set in the pushed flags; a later one waits
+	// until the BRK handler has run an instruction.
+	let source = "
+		.org $0400
+	start:
+		BRK
+		.byte $00
+	";
+	for (edge, vector) in [(0, NMI_HANDLER), (4, NMI_HANDLER), (5, IRQ_HANDLER)] {
+		each_mode(|mode| {
+			let (mut cpu, mut ram) = boot_with_handlers(mode, source);
+			cpu.sr.insert(Status::I);
+
+			for clock in 0..7 {
+				if clock == edge {
+					cpu.set_nmi(true);
+				}
+				cpu.cycle(&mut ram);
+			}
+			assert!(cpu.at_boundary(), "{:?}", mode);
+			assert_eq!(cpu.pc, vector, "{:?}, edge after cycle {}", mode, edge);
+
+			let (pc, status) = stacked(&cpu, &ram);
+			assert_eq!(pc, 0x0402, "{:?}", mode);
+			assert!(status.contains(Status::B), "{:?}", mode);
+
+			// A lost NMI would never reach its handler
+			if vector == IRQ_HANDLER {
+				cpu.step_instruction(&mut ram);
+				assert_eq!(cpu.step_instruction(&mut ram).pc, IRQ_HANDLER + 2, "{:?}", mode);
+				assert_eq!(cpu.pc, NMI_HANDLER, "{:?}", mode);
+			}
+		});
+	}
+}
+
+#[test]
+fn nmi_hijacks_irq() {
+	// The IRQ is polled before cycle 2 and runs its sequence on cycles 3-9.
+	// An NMI edge up to cycle 6 takes over the vector fetch, with B clear.
+	for (edge, vector) in [(5, NMI_HANDLER), (6, NMI_HANDLER), (7, IRQ_HANDLER)] {
+		each_mode(|mode| {
+			let (mut cpu, mut ram) = boot_with_handlers(mode, NOPS);
+			cpu.set_irq(IrqSource::External, true);
+
+			let clock = run_to(&mut cpu, &mut ram, vector, |cpu, clock| {
+				if clock == edge {
+					cpu.set_nmi(true);
+				}
+			});
+			assert_eq!(clock, 9, "{:?}, edge after cycle {}", mode, edge);
+
+			let (pc, status) = stacked(&cpu, &ram);
+			assert_eq!(pc, 0x0401, "{:?}", mode);
+			assert!(!status.contains(Status::B), "{:?}", mode);
+		});
+	}
+}
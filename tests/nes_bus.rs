@@ -0,0 +1,21 @@
+// The CPU's view of the NES address space
+
+
+use nes::bus::Bus;
+use nes::nes::NesBus;
+
+#[test]
+fn ram_mirrors_every_2k() {
+	let mut bus = NesBus::init();
+	bus.write(0x0001, 0x5A);
+
+	for addr in [0x0001, 0x0801, 0x1001, 0x1801] {
+		assert_eq!(bus.read(addr), 0x5A, "${:04X}", addr);
+		assert_eq!(bus.peek(addr), 0x5A, "${:04X}", addr);
+	}
+
+	// Writes through a mirror land in the same byte
+	bus.write(0x1FFF, 0xA5);
+	assert_eq!(bus.read(0x07FF), 0xA5);
+	assert_eq!(bus.ram[0x07FF], 0xA5);
+}
@@ -0,0 +1,491 @@
+// PPU registers as the CPU sees them through the NES bus, the PPU's own
+// address space, its frame timing and rendering
+
+
+use nes::asm::assemble;
+use nes::bus::Bus;
+use nes::cartridge::{Mirroring, Nrom};
+use nes::nes::{Nes, NesBus};
+use nes::cartridge::Cartridge;
+use nes::ppu::{
+	Ppu, CTRL_SPRITE_8X16, MASK_BACKGROUND, MASK_BACKGROUND_LEFT, MASK_SPRITES, MASK_SPRITES_LEFT,
+	SCREEN_HEIGHT, SCREEN_WIDTH, STATUS_SPRITE_OVERFLOW, STATUS_SPRITE_ZERO_HIT, STATUS_VBLANK,
+};
+
+fn bus_with_nrom(mirroring: Mirroring, chr_ram: bool) -> NesBus {
+	let mut bus = NesBus::init();
+	let chr = (0..8*1024).map(|i| i as u8).collect();
+	bus.cart = Some(Box::new(Nrom { prg_rom: vec![0; 16*1024], prg_ram: [0; 8*1024], chr, chr_ram, mirroring }));
+	bus
+}
+
+fn set_address(bus: &mut NesBus, addr: u16) {
+	bus.write(0x2006, (addr >> 8) as u8);
+	bus.write(0x2006, addr as u8);
+}
+
+#[test]
+fn ppudata_reads_are_buffered() {
+	let mut bus = NesBus::init();
+	set_address(&mut bus, 0x2400);
+	for data in [0x11, 0x22, 0x33] {
+		bus.write(0x2007, data);
+	}
+
+	set_address(&mut bus, 0x2400);
+	let stale = bus.read(0x2007);
+	assert_eq!([bus.read(0x2007), bus.read(0x2007)], [0x11, 0x22]);
+	assert_eq!(stale, 0x00);
+}
+
+#[test]
+fn palette_reads_are_immediate() {
+	let mut bus = NesBus::init();
+	set_address(&mut bus, 0x3F01);
+	bus.write(0x2007, 0x2A);
+
+	set_address(&mut bus, 0x3F01);
+	assert_eq!(bus.read(0x2007), 0x2A);
+}
+
+#[test]
+fn ppudata_increments_by_1_or_32() {
+	let mut bus = NesBus::init();
+	set_address(&mut bus, 0x2000);
+	bus.write(0x2007, 0xAA);
+	bus.write(0x2007, 0xBB);
+	assert_eq!(bus.ppu.vram_addr, 0x2002);
+
+	bus.write(0x2000, 0x04);
+	bus.write(0x2007, 0xCC);
+	assert_eq!(bus.ppu.vram_addr, 0x2022);
+	assert_eq!(bus.ppu.ppu_read(&mut None, 0x2001), 0xBB);
+	assert_eq!(bus.ppu.ppu_read(&mut None, 0x2002), 0xCC);
+}
+
+#[test]
+fn status_read_clears_vblank_and_write_toggle() {
+	let mut bus = NesBus::init();
+	bus.ppu.status = STATUS_VBLANK;
+
+	// Half a PPUADDR write, abandoned by the status read
+	bus.write(0x2006, 0x3F);
+	assert_eq!(bus.read(0x2002) & STATUS_VBLANK, STATUS_VBLANK);
+	assert_eq!(bus.read(0x2002) & STATUS_VBLANK, 0);
+
+	set_address(&mut bus, 0x2155);
+	assert_eq!(bus.ppu.vram_addr, 0x2155);
+}
+
+#[test]
+fn scroll_and_address_share_the_toggle() {
+	let mut bus = NesBus::init();
+	// The first PPUADDR write lands as the second half of the pair
+	bus.write(0x2005, 0x12);
+	bus.write(0x2006, 0x21);
+	bus.write(0x2006, 0x08);
+	assert_eq!(bus.ppu.fine_x, 0x02);
+	assert_eq!(bus.ppu.vram_addr, 0x0021);
+}
+
+#[test]
+fn oamdata_writes_increment_oamaddr() {
+	let mut bus = NesBus::init();
+	bus.write(0x2003, 0xFE);
+	bus.write(0x2004, 0x01);
+	bus.write(0x2004, 0x02);
+	bus.write(0x2004, 0x03);
+	assert_eq!((bus.ppu.oam[0xFE], bus.ppu.oam[0xFF], bus.ppu.oam[0x00]), (0x01, 0x02, 0x03));
+
+	// Reads do not
+	bus.write(0x2003, 0xFF);
+	assert_eq!([bus.read(0x2004), bus.read(0x2004)], [0x02, 0x02]);
+}
+
+#[test]
+fn registers_mirror_every_8_bytes() {
+	let mut bus = NesBus::init();
+	bus.write(0x3FFE, 0x23);
+	bus.write(0x3FFE, 0x45);
+	bus.write(0x200F, 0x67);
+	assert_eq!(bus.ppu.ppu_read(&mut None, 0x2345), 0x67);
+	assert_eq!(bus.peek(0x3FFA) & STATUS_VBLANK, 0);
+}
+
+
+//
+// Address Space
+
+#[test]
+fn pattern_tables_come_from_the_cartridge() {
+	let mut bus = bus_with_nrom(Mirroring::Horizontal, false);
+	assert_eq!(bus.ppu.ppu_read(&mut bus.cart, 0x1234), 0x34);
+
+	// CHR-ROM ignores writes
+	bus.ppu.ppu_write(&mut bus.cart, 0x1234, 0xFF);
+	assert_eq!(bus.ppu.ppu_read(&mut bus.cart, 0x1234), 0x34);
+
+	let mut bus = bus_with_nrom(Mirroring::Horizontal, true);
+	set_address(&mut bus, 0x0010);
+	bus.write(0x2007, 0xEE);
+	assert_eq!(bus.ppu.ppu_read(&mut bus.cart, 0x0010), 0xEE);
+}
+
+#[test]
+fn nametable_mirroring() {
+	for (mirroring, same, different) in [
+		(Mirroring::Horizontal, 0x2400, 0x2800),
+		(Mirroring::Vertical, 0x2800, 0x2400),
+	] {
+		let mut bus = bus_with_nrom(mirroring, false);
+		bus.ppu.ppu_write(&mut bus.cart, 0x2005, 0x5A);
+
+		assert_eq!(bus.ppu.ppu_read(&mut bus.cart, same + 0x0005), 0x5A, "{:?}", mirroring);
+		assert_eq!(bus.ppu.ppu_read(&mut bus.cart, different + 0x0005), 0x00, "{:?}", mirroring);
+		assert_eq!(bus.ppu.ppu_read(&mut bus.cart, 0x3005), 0x5A, "{:?}", mirroring);
+	}
+}
+
+#[test]
+fn palette_mirrors() {
+	let mut bus = NesBus::init();
+	bus.ppu.ppu_write(&mut None, 0x3F10, 0x0F);
+	bus.ppu.ppu_write(&mut None, 0x3F05, 0x16);
+
+	assert_eq!(bus.ppu.ppu_read(&mut None, 0x3F00), 0x0F);
+	assert_eq!(bus.ppu.ppu_read(&mut None, 0x3F25), 0x16);
+	assert_eq!(bus.ppu.ppu_read(&mut None, 0x3F15), 0x00);
+}
+
+
+//
+// Timing
+
+fn clock_to(ppu: &mut Ppu, scanline: u16, dot: u16) {
+	while (ppu.scanline, ppu.dot) != (scanline, dot) {
+		ppu.clock(&mut None);
+	}
+}
+
+// Dots until the frame counter next moves on
+fn frame_length(ppu: &mut Ppu) -> u32 {
+	let frame = ppu.frame;
+	let mut dots = 0;
+	while ppu.frame == frame {
+		ppu.clock(&mut None);
+		dots += 1;
+	}
+	dots
+}
+
+#[test]
+fn vblank_flag_timing() {
+	let mut ppu = Ppu::init();
+
+	clock_to(&mut ppu, 241, 1);
+	assert_eq!(ppu.status & STATUS_VBLANK, 0);
+	ppu.clock(&mut None);
+	assert_eq!(ppu.status & STATUS_VBLANK, STATUS_VBLANK);
+
+	clock_to(&mut ppu, 261, 1);
+	assert_eq!(ppu.status & STATUS_VBLANK, STATUS_VBLANK);
+	ppu.clock(&mut None);
+	assert_eq!(ppu.status & STATUS_VBLANK, 0);
+}
+
+#[test]
+fn odd_frames_are_one_dot_shorter_when_rendering() {
+	let mut ppu = Ppu::init();
+	assert_eq!([frame_length(&mut ppu), frame_length(&mut ppu)], [341*262, 341*262]);
+
+	ppu.mask = MASK_BACKGROUND;
+	assert_eq!([frame_length(&mut ppu), frame_length(&mut ppu)], [341*262, 341*262 - 1]);
+}
+
+#[test]
+fn vblank_raises_nmi() {
+	// Counts NMIs in $10 once PPUCTRL enables them
+	let program = assemble("
+		.org $8000
+	reset:
+		LDA #$80
+		STA $2000
+	loop:
+		JMP loop
+	nmi:
+		INC $10
+		RTI
+		.org $FFFA
+		.word nmi, reset, reset
+	").unwrap();
+
+	let mut prg_rom = vec![0; 32*1024];
+	for chunk in &program.chunks {
+		let start = chunk.addr as usize - 0x8000;
+		prg_rom[start..start + chunk.bytes.len()].copy_from_slice(&chunk.bytes);
+	}
+
+	let mut nes = Nes::init();
+	nes.insert_cartridge(Box::new(Nrom { prg_rom, prg_ram: [0; 8*1024], chr: vec![0; 8*1024], chr_ram: true, mirroring: Mirroring::Horizontal }));
+	nes.power_on();
+
+	for _ in 0..3*341*262 {
+		nes.clock();
+	}
+	assert_eq!(nes.bus.ram[0x10], 3);
+	assert_eq!(nes.bus.ppu.frame, 3);
+
+	// Reading PPUSTATUS acknowledges the flag but does not hold off NMI
+	// for the next frame
+	nes.bus.read(0x2002);
+	for _ in 0..341*262 {
+		nes.clock();
+	}
+	assert_eq!(nes.bus.ram[0x10], 4);
+}
+
+
+//
+// Background
+
+const BACKDROP: u8 = 0x0F;
+const WHITE: u8 = 0x30;
+const RED: u8 = 0x16;
+
+// Vertical stripes 8 pixels wide: tile 1 (colour 1, white) in even
+// columns, tile 0 (transparent) in odd ones. The bottom-right quadrant of
+// every 32x32 block uses the second palette, where colour 1 is red.
+fn striped_screen() -> (Ppu, Option<Box<dyn Cartridge>>) {
+	let mut cart: Option<Box<dyn Cartridge>> = Some(Box::new(Nrom {
+		prg_rom: vec![0; 16*1024], prg_ram: [0; 8*1024], chr: vec![0; 8*1024], chr_ram: true, mirroring: Mirroring::Vertical,
+	}));
+	let mut ppu = Ppu::init();
+
+	for row in 0..8 {
+		ppu.ppu_write(&mut cart, 0x0010 + row, 0xFF);
+	}
+	for table in [0x2000, 0x2400] {
+		for tile in 0..960 {
+			ppu.ppu_write(&mut cart, table + tile, if tile % 2 == 0 { 0x01 } else { 0x00 });
+		}
+		for attrib in 0..64 {
+			ppu.ppu_write(&mut cart, table + 0x03C0 + attrib, 0b01_00_00_00);
+		}
+	}
+	ppu.ppu_write(&mut cart, 0x3F00, BACKDROP);
+	ppu.ppu_write(&mut cart, 0x3F01, WHITE);
+	ppu.ppu_write(&mut cart, 0x3F05, RED);
+
+	ppu.mask = MASK_BACKGROUND | MASK_BACKGROUND_LEFT;
+	(ppu, cart)
+}
+
+fn stripe_colour(x: usize, y: usize) -> u8 {
+	match ((x / 8) % 2, (x / 16) % 2 == 1 && (y / 16) % 2 == 1) {
+		(1, _) => BACKDROP,
+		(_, true) => RED,
+		(_, false) => WHITE,
+	}
+}
+
+// Runs from the pre-render line through the last visible line, calling
+// hblank in the horizontal blank of every line
+fn render_frame(ppu: &mut Ppu, cart: &mut Option<Box<dyn Cartridge>>, mut hblank: impl FnMut(&mut Ppu, &mut Option<Box<dyn Cartridge>>)) {
+	while (ppu.scanline, ppu.dot) != (261, 0) {
+		ppu.clock(cart);
+	}
+	while (ppu.scanline, ppu.dot) != (240, 0) {
+		if ppu.dot == 260 {
+			hblank(ppu, cart);
+		}
+		ppu.clock(cart);
+	}
+}
+
+fn check_screen(ppu: &Ppu, expected: impl Fn(usize, usize) -> u8) {
+	for y in 0..SCREEN_HEIGHT {
+		for x in 0..SCREEN_WIDTH {
+			assert_eq!(ppu.screen[y*SCREEN_WIDTH + x], expected(x, y), "pixel {}, {}", x, y);
+		}
+	}
+}
+
+#[test]
+fn background_renders_tiles_and_attributes() {
+	let (mut ppu, mut cart) = striped_screen();
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+	check_screen(&ppu, stripe_colour);
+}
+
+#[test]
+fn background_scroll() {
+	let (mut ppu, mut cart) = striped_screen();
+
+	// 11 pixels right (coarse 1, fine 3), 5 down. The bottom lines come
+	// from the top of the nametable below.
+	ppu.cpu_write(&mut cart, 0x2005, 11);
+	ppu.cpu_write(&mut cart, 0x2005, 5);
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+
+	check_screen(&ppu, |x, y| stripe_colour(x + 11, (y + 5) % SCREEN_HEIGHT));
+}
+
+#[test]
+fn mid_frame_scroll_split() {
+	let (mut ppu, mut cart) = striped_screen();
+
+	// A status bar at the top stays put while the rest scrolls. Written in
+	// the horizontal blank of line 99, fine X applies from line 100 but the
+	// coarse X only reaches v at dot 257 of line 100.
+	render_frame(&mut ppu, &mut cart, |ppu, cart| {
+		if ppu.scanline == 99 {
+			ppu.cpu_write(cart, 0x2005, 12);
+			ppu.cpu_write(cart, 0x2005, 0);
+		}
+	});
+
+	check_screen(&ppu, |x, y| match y {
+		0..=99 => stripe_colour(x, y),
+		100 => stripe_colour(x + 4, y),
+		_ => stripe_colour(x + 12, y),
+	});
+}
+
+#[test]
+fn left_column_clipping() {
+	let (mut ppu, mut cart) = striped_screen();
+	ppu.mask = MASK_BACKGROUND;
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+
+	check_screen(&ppu, |x, y| if x < 8 { BACKDROP } else { stripe_colour(x, y) });
+}
+
+
+//
+// Sprites
+
+// Empty background except one solid tile covering 40-47, 40-47. Tile 2 has
+// only the top-left 4 pixels set, tile 3 the bottom-right 4; both draw in
+// red with the first sprite palette. Unused OAM entries sit below the screen.
+fn sprite_screen(sprites: &[[u8; 4]]) -> (Ppu, Option<Box<dyn Cartridge>>) {
+	let mut cart: Option<Box<dyn Cartridge>> = Some(Box::new(Nrom {
+		prg_rom: vec![0; 16*1024], prg_ram: [0; 8*1024], chr: vec![0; 8*1024], chr_ram: true, mirroring: Mirroring::Vertical,
+	}));
+	let mut ppu = Ppu::init();
+
+	for row in 0..8 {
+		ppu.ppu_write(&mut cart, 0x0010 + row, 0xFF);
+	}
+	ppu.ppu_write(&mut cart, 0x0020, 0xF0);
+	ppu.ppu_write(&mut cart, 0x0037, 0x0F);
+	ppu.ppu_write(&mut cart, 0x2000 + 5*32 + 5, 0x01);
+
+	ppu.ppu_write(&mut cart, 0x3F00, BACKDROP);
+	ppu.ppu_write(&mut cart, 0x3F01, WHITE);
+	ppu.ppu_write(&mut cart, 0x3F11, RED);
+
+	ppu.oam = [0xFF; 256];
+	for (n, sprite) in sprites.iter().enumerate() {
+		ppu.oam[n*4..n*4 + 4].copy_from_slice(sprite);
+	}
+
+	ppu.mask = MASK_BACKGROUND | MASK_BACKGROUND_LEFT | MASK_SPRITES | MASK_SPRITES_LEFT;
+	(ppu, cart)
+}
+
+fn pixel(ppu: &Ppu, x: usize, y: usize) -> u8 {
+	ppu.screen[y*SCREEN_WIDTH + x]
+}
+
+// Colours of the 8 pixels from x on line y
+fn row(ppu: &Ppu, x: usize, y: usize) -> [u8; 8] {
+	std::array::from_fn(|i| pixel(ppu, x + i, y))
+}
+
+const R: u8 = RED;
+const B: u8 = BACKDROP;
+
+#[test]
+fn sprite_position_and_flips() {
+	let (mut ppu, mut cart) = sprite_screen(&[
+		[49, 2, 0x00, 20],		// Shows from line 50
+		[99, 2, 0x40, 20],		// Flipped horizontally
+		[149, 2, 0x80, 20],		// Flipped vertically
+	]);
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+
+	assert_eq!(row(&ppu, 20, 49), [B; 8]);
+	assert_eq!(row(&ppu, 20, 50), [R, R, R, R, B, B, B, B]);
+	assert_eq!(row(&ppu, 20, 51), [B; 8]);
+
+	assert_eq!(row(&ppu, 20, 100), [B, B, B, B, R, R, R, R]);
+
+	assert_eq!(row(&ppu, 20, 150), [B; 8]);
+	assert_eq!(row(&ppu, 20, 157), [R, R, R, R, B, B, B, B]);
+}
+
+#[test]
+fn eight_sprites_per_line() {
+	let sprites: Vec<[u8; 4]> = (0..9).map(|n| [49, 2, 0x00, n * 16]).collect();
+	let (mut ppu, mut cart) = sprite_screen(&sprites);
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+
+	for n in 0..8 {
+		assert_eq!(pixel(&ppu, n*16, 50), RED, "sprite {}", n);
+	}
+	assert_eq!(pixel(&ppu, 128, 50), BACKDROP);
+	assert_eq!(ppu.status & STATUS_SPRITE_OVERFLOW, STATUS_SPRITE_OVERFLOW);
+
+	let (mut ppu, mut cart) = sprite_screen(&sprites[..8]);
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+	assert_eq!(ppu.status & STATUS_SPRITE_OVERFLOW, 0);
+}
+
+#[test]
+fn sprite_priority_and_sprite_zero_hit() {
+	// Sprite 0 behind the solid tile, sprite 1 in front of it
+	let (mut ppu, mut cart) = sprite_screen(&[
+		[40, 2, 0x20, 40],
+		[42, 2, 0x00, 40],
+	]);
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+
+	assert_eq!(row(&ppu, 40, 41), [WHITE; 8]);
+	assert_eq!(row(&ppu, 40, 43), [R, R, R, R, WHITE, WHITE, WHITE, WHITE]);
+	assert_eq!(ppu.status & STATUS_SPRITE_ZERO_HIT, STATUS_SPRITE_ZERO_HIT);
+
+	// Over transparent background there is no hit
+	let (mut ppu, mut cart) = sprite_screen(&[[40, 2, 0x00, 100]]);
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+	assert_eq!(ppu.status & STATUS_SPRITE_ZERO_HIT, 0);
+}
+
+#[test]
+fn sprite_left_column_clipping() {
+	let (mut ppu, mut cart) = sprite_screen(&[[49, 2, 0x40, 0]]);
+	ppu.mask &= !MASK_SPRITES_LEFT;
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+	assert_eq!(row(&ppu, 0, 50), [B; 8]);
+
+	let (mut ppu, mut cart) = sprite_screen(&[[49, 2, 0x40, 4]]);
+	ppu.mask &= !MASK_SPRITES_LEFT;
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+	assert_eq!(row(&ppu, 4, 50), [B, B, B, B, R, R, R, R]);
+}
+
+#[test]
+fn tall_sprites() {
+	let (mut ppu, mut cart) = sprite_screen(&[
+		[49, 2, 0x00, 60],
+		[99, 2, 0x80, 60],		// Flipping swaps the two tiles too
+	]);
+	ppu.ctrl = CTRL_SPRITE_8X16;
+	render_frame(&mut ppu, &mut cart, |_, _| ());
+
+	assert_eq!(row(&ppu, 60, 50), [R, R, R, R, B, B, B, B]);
+	assert_eq!(row(&ppu, 60, 65), [B, B, B, B, R, R, R, R]);
+	assert_eq!(row(&ppu, 60, 66), [B; 8]);
+
+	assert_eq!(row(&ppu, 60, 100), [B, B, B, B, R, R, R, R]);
+	assert_eq!(row(&ppu, 60, 115), [R, R, R, R, B, B, B, B]);
+}
@@ -0,0 +1,120 @@
+// Tom Harte's per-opcode CPU tests:
+//	https://github.com/SingleStepTests/ProcessorTests/tree/main/nes6502
+//
+// Expects the JSON files (00.json .. ff.json) in tests/roms/nes6502, or
+// <NES_TEST_ROMS>/nes6502. They are not part of the repository, so the test
+// is ignored by default (cargo test -- --ignored) and fails if any file is
+// missing. Each case gives the state before and after one instruction plus
+// every bus access it makes. Both cores must reach the final state in the
+// right number of cycles; the cycle-accurate core must also reproduce the
+// bus activity cycle by cycle.
+//
+// XAA and LXA depend on a magic constant that varies from chip to chip;
+// the cores use $EE, as these tests do, so they run like every other opcode.
+//
+// There are 10,000 cases per opcode; SINGLE_STEP_LIMIT caps the cases
+// taken from each file.
+
+
+mod common;
+
+use serde_json::Value;
+
+use common::Recorder;
+use nes::cpu::{Cpu, Status, StepMode, LOOK_UP};
+
+fn field(state: &Value, name: &str) -> u16 {
+	state[name].as_u64().unwrap_or_else(|| panic!("missing field {}", name)) as u16
+}
+
+fn ram(state: &Value) -> Vec<(u16, u8)> {
+	state["ram"].as_array().unwrap().iter()
+		.map(|entry| (entry[0].as_u64().unwrap() as u16, entry[1].as_u64().unwrap() as u8))
+		.collect()
+}
+
+// Runs one case in the given core, describing the first difference found
+fn run_case(case: &Value, mode: StepMode) -> Result<(), String> {
+	let initial = &case["initial"];
+	let expected = &case["final"];
+
+	let mut bus = Recorder::init();
+	for (addr, data) in ram(initial) {
+		bus.mem[addr as usize] = data;
+	}
+
+	let mut cpu = Cpu::init();
+	cpu.mode = mode;
+	cpu.pc = field(initial, "pc");
+	cpu.sp = field(initial, "s") as u8;
+	cpu.ac = field(initial, "a") as u8;
+	cpu.x = field(initial, "x") as u8;
+	cpu.y = field(initial, "y") as u8;
+	cpu.sr = Status::from_bits(field(initial, "p") as u8);
+
+	let step = cpu.step_instruction(&mut bus);
+
+	// B and bit 5 are not stored in P
+	let p_mask = !(Status::B | Status::U).bits();
+	let actual = (cpu.pc, cpu.sp, cpu.ac, cpu.x, cpu.y, cpu.sr.bits() & p_mask);
+	let wanted = (
+		field(expected, "pc"), field(expected, "s") as u8, field(expected, "a") as u8,
+		field(expected, "x") as u8, field(expected, "y") as u8, field(expected, "p") as u8 & p_mask,
+	);
+	if actual != wanted {
+		return Err(format!("registers (pc, s, a, x, y, p) {:X?}, expected {:X?}", actual, wanted));
+	}
+
+	for (addr, data) in ram(expected) {
+		if bus.mem[addr as usize] != data {
+			return Err(format!("${:04X} = {:02X}, expected {:02X}", addr, bus.mem[addr as usize], data));
+		}
+	}
+
+	let cycles: Vec<(u16, u8, String)> = case["cycles"].as_array().unwrap().iter()
+		.map(|c| (c[0].as_u64().unwrap() as u16, c[1].as_u64().unwrap() as u8, c[2].as_str().unwrap().to_string()))
+		.collect();
+	if step.cycles as usize != cycles.len() {
+		return Err(format!("took {} cycles, expected {}", step.cycles, cycles.len()));
+	}
+
+	if mode == StepMode::Cycle {
+		let log: Vec<(u16, u8, String)> = bus.log.iter().map(|&(a, d, k)| (a, d, k.to_string())).collect();
+		if log != cycles {
+			return Err(format!("bus activity {:X?}, expected {:X?}", log, cycles));
+		}
+	}
+
+	Ok(())
+}
+
+#[test]
+#[ignore = "needs the SingleStepTests nes6502 JSON files"]
+fn single_step_tests() {
+	let dir = common::roms_dir().join("nes6502");
+
+	let limit = std::env::var("SINGLE_STEP_LIMIT").ok().and_then(|n| n.parse().ok()).unwrap_or(usize::MAX);
+	let mut failures = Vec::new();
+
+	for opcode in 0..=255u8 {
+		if LOOK_UP[opcode as usize].mnemonic == "JAM" {
+			continue;
+		}
+
+		let path = dir.join(format!("{:02x}.json", opcode));
+		let text = std::fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+		let cases: Value = serde_json::from_str(&text).unwrap();
+
+		for case in cases.as_array().unwrap().iter().take(limit) {
+			for mode in [StepMode::Instruction, StepMode::Cycle] {
+				if let Err(message) = run_case(case, mode) {
+					failures.push(format!("{:02X} {} [{}] {:?}: {}", opcode, LOOK_UP[opcode as usize].mnemonic, case["name"], mode, message));
+				}
+			}
+		}
+	}
+
+	if !failures.is_empty() {
+		panic!("{} failing cases, first ones:\n{}", failures.len(), failures[..failures.len().min(50)].join("\n"));
+	}
+}
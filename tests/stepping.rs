@@ -0,0 +1,201 @@
+// Halting and the stepping API
+//
+// JAM opcodes lock the CPU up until reset. step_instruction, run_cycles,
+// run_until_pc and run_until drive the CPU a whole instruction (or a fixed
+// number of cycles) at a time. Every case runs in both cores.
+
+
+mod common;
+
+use common::{boot, boot_variant, each_mode};
+use nes::cpu::{CpuState, Interrupt, IrqSource, Status, Variant, LOOK_UP};
+
+
+//
+// Halting
+
+#[test]
+fn jam_halts_until_reset() {
+	let jams = (0..=255u8).filter(|&opcode| LOOK_UP[opcode as usize].mnemonic == "JAM");
+
+	for opcode in jams {
+		each_mode(|mode| {
+			let source = format!("
+				.org $0400
+			start:
+				NOP
+				.byte ${:02X}
+				.org $0500
+			reset:
+				NOP
+				.org $FFFC
+				.word reset
+			", opcode);
+			let (mut cpu, mut ram) = boot(mode, &source);
+
+			cpu.step_instruction(&mut ram);
+			cpu.step_instruction(&mut ram);
+			let halted = CpuState::Halted { pc: 0x0401, opcode };
+			assert_eq!(cpu.state, halted, "{:?} {:02X}", mode, opcode);
+
+			// Neither more cycles nor interrupts get it going again
+			cpu.set_nmi(true);
+			assert_eq!(cpu.run_cycles(&mut ram, 100), halted, "{:?} {:02X}", mode, opcode);
+			let step = cpu.step_instruction(&mut ram);
+			assert_eq!(step.cycles, 0, "{:?} {:02X}", mode, opcode);
+			assert_eq!(cpu.pc, 0x0401, "{:?} {:02X}", mode, opcode);
+
+			cpu.reset(&mut ram);
+			assert_eq!(cpu.state, CpuState::Running, "{:?} {:02X}", mode, opcode);
+			assert_eq!(cpu.step_instruction(&mut ram).pc, 0x0500, "{:?} {:02X}", mode, opcode);
+		});
+	}
+}
+
+
+//
+// Stepping
+
+const PROGRAM: &str = "
+	.org $0400
+start:
+	LDA $12F0,X
+	NOP
+loop:
+	INC $10
+	JMP loop
+	.org $0600
+nmi:
+	RTI
+	.org $FFFA
+	.word nmi
+";
+
+#[test]
+fn step_reports_the_instruction() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, PROGRAM);
+		cpu.x = 0x20;
+
+		let step = cpu.step_instruction(&mut ram);
+		assert_eq!((step.pc, step.opcode, step.cycles, step.interrupt), (0x0400, 0xBD, 5, None), "{:?}", mode);
+		assert_eq!(cpu.global_clock, 5, "{:?}", mode);
+
+		let step = cpu.step_instruction(&mut ram);
+		assert_eq!((step.pc, step.opcode, step.cycles, step.interrupt), (0x0403, 0xEA, 2, None), "{:?}", mode);
+	});
+}
+
+#[test]
+fn step_reports_interrupt_sequences() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, PROGRAM);
+		cpu.set_nmi(true);
+
+		// The NMI is polled during LDA and taken after it
+		let step = cpu.step_instruction(&mut ram);
+		assert_eq!(step.interrupt, None, "{:?}", mode);
+
+		let step = cpu.step_instruction(&mut ram);
+		assert_eq!((step.pc, step.opcode, step.cycles, step.interrupt), (0x0403, 0x00, 7, Some(Interrupt::Nmi)), "{:?}", mode);
+		assert_eq!(cpu.pc, 0x0600, "{:?}", mode);
+
+		let step = cpu.step_instruction(&mut ram);
+		assert_eq!((step.pc, step.opcode, step.interrupt), (0x0600, 0x40, None), "{:?}", mode);
+	});
+}
+
+#[test]
+fn step_finishes_the_instruction_in_flight() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, PROGRAM);
+		cpu.x = 0x20;
+
+		// One cycle into LDA: its other 4 run first and are not counted
+		cpu.cycle(&mut ram);
+		assert!(!cpu.at_boundary(), "{:?}", mode);
+
+		let step = cpu.step_instruction(&mut ram);
+		assert_eq!((step.pc, step.opcode, step.cycles), (0x0403, 0xEA, 2), "{:?}", mode);
+		assert_eq!(cpu.global_clock, 7, "{:?}", mode);
+		assert_eq!(cpu.pc, 0x0404, "{:?}", mode);
+	});
+}
+
+#[test]
+fn run_cycles_runs_exactly_n() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, PROGRAM);
+
+		assert_eq!(cpu.run_cycles(&mut ram, 3), CpuState::Running, "{:?}", mode);
+		assert_eq!(cpu.global_clock, 3, "{:?}", mode);
+	});
+}
+
+#[test]
+fn run_until_pc() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, PROGRAM);
+
+		// Already there: nothing runs
+		assert_eq!(cpu.run_until_pc(&mut ram, 0x0400), CpuState::Running, "{:?}", mode);
+		assert_eq!(cpu.global_clock, 0, "{:?}", mode);
+
+		assert_eq!(cpu.run_until_pc(&mut ram, 0x0404), CpuState::Running, "{:?}", mode);
+		assert_eq!((cpu.pc, cpu.global_clock), (0x0404, 6), "{:?}", mode);
+		assert!(cpu.at_boundary(), "{:?}", mode);
+	});
+}
+
+#[test]
+fn run_until_sees_the_bus() {
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, PROGRAM);
+
+		cpu.run_until(&mut ram, |_, bus| bus.peek(0x0010) == 3);
+		assert_eq!(ram.mem[0x10], 3, "{:?}", mode);
+		assert_eq!(cpu.pc, 0x0406, "{:?}", mode);
+	});
+}
+
+#[test]
+fn run_until_stops_on_jam() {
+	let source = "
+		.org $0400
+	start:
+		NOP
+		.byte $02
+	";
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot(mode, source);
+
+		let state = cpu.run_until_pc(&mut ram, 0x1234);
+		assert_eq!(state, CpuState::Halted { pc: 0x0401, opcode: 0x02 }, "{:?}", mode);
+	});
+}
+
+#[test]
+fn run_until_stops_on_wai() {
+	// WAI, NOP on a 65C02; the assembler only knows the NMOS opcodes
+	let source = "
+		.org $0400
+	start:
+		.byte $CB, $EA
+	";
+	each_mode(|mode| {
+		let (mut cpu, mut ram) = boot_variant(Variant::Wdc65C02, mode, source);
+		cpu.sr.insert(Status::I);
+
+		// Nothing can wake it, so the loop gives up after WAI
+		assert_eq!(cpu.run_until_pc(&mut ram, 0x0402), CpuState::Waiting, "{:?}", mode);
+		assert_eq!((cpu.pc, cpu.global_clock), (0x0401, 3), "{:?}", mode);
+		assert!(cpu.is_stopped(), "{:?}", mode);
+		assert_eq!(cpu.step_instruction(&mut ram).cycles, 0, "{:?}", mode);
+
+		// A masked IRQ still ends the wait, without being serviced
+		cpu.set_irq(IrqSource::External, true);
+		assert!(!cpu.is_stopped(), "{:?}", mode);
+		assert_eq!(cpu.run_until_pc(&mut ram, 0x0402), CpuState::Running, "{:?}", mode);
+		assert_eq!(cpu.pc, 0x0402, "{:?}", mode);
+	});
+}
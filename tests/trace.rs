@@ -0,0 +1,76 @@
+// Trace lines in the format of nestest.log
+//	https://www.qmtpro.com/~nes/misc/nestest.log
+
+
+use nes::bus::FlatRam;
+use nes::cpu::{Cpu, Status};
+
+// Cpu about to run bytes at pc, with the registers and cycle count given
+fn at(pc: u16, bytes: &[u8], (ac, x, y, sr, sp): (u8, u8, u8, u8, u8), cyc: u128) -> (Cpu, FlatRam) {
+	let mut ram = FlatRam::init();
+	ram.mem[pc as usize..pc as usize + bytes.len()].copy_from_slice(bytes);
+
+	let mut cpu = Cpu::init();
+	cpu.pc = pc;
+	(cpu.ac, cpu.x, cpu.y, cpu.sp) = (ac, x, y, sp);
+	cpu.sr = Status::from_bits(sr);
+	// CYC counts the cycles before the opcode fetch
+	cpu.global_clock = cyc + 1;
+
+	(cpu, ram)
+}
+
+#[test]
+fn first_nestest_line() {
+	let (cpu, ram) = at(0xC000, &[0x4C, 0xF5, 0xC5], (0x00, 0x00, 0x00, 0x24, 0xFD), 7);
+	assert_eq!(
+		cpu.trace_line(&ram),
+		"C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7",
+	);
+}
+
+#[test]
+fn indirect_jmp() {
+	// The pointer's high byte comes from $0200, not $0300
+	let (cpu, mut ram) = at(0xC100, &[0x6C, 0xFF, 0x02], (0x10, 0x20, 0x30, 0xA4, 0xFB), 100);
+	ram.mem[0x02FF] = 0x00;
+	ram.mem[0x0200] = 0x03;
+	ram.mem[0x0300] = 0x04;
+
+	assert_eq!(
+		cpu.trace_line(&ram),
+		"C100  6C FF 02  JMP ($02FF) = 0300              A:10 X:20 Y:30 P:A4 SP:FB PPU:  0,300 CYC:100",
+	);
+}
+
+#[test]
+fn indirect_indexed() {
+	let (cpu, mut ram) = at(0xC200, &[0xB1, 0x89], (0x00, 0x00, 0x34, 0x26, 0xF9), 1000);
+	ram.mem[0x89] = 0x00;
+	ram.mem[0x8A] = 0x03;
+	ram.mem[0x0334] = 0x5A;
+
+	assert_eq!(
+		cpu.trace_line(&ram),
+		"C200  B1 89     LDA ($89),Y = 0300 @ 0334 = 5A  A:00 X:00 Y:34 P:26 SP:F9 PPU:  8,272 CYC:1000",
+	);
+}
+
+#[test]
+fn unofficial_opcodes() {
+	// Marked with *, and ISC under nestest's name for it
+	let (cpu, ram) = at(0xC300, &[0x04, 0xA9], (0xAA, 0x97, 0x4E, 0xEF, 0xF5), 12345);
+	assert_eq!(
+		cpu.trace_line(&ram),
+		"C300  04 A9    *NOP $A9 = 00                    A:AA X:97 Y:4E P:EF SP:F5 PPU:108,207 CYC:12345",
+	);
+
+	let (cpu, mut ram) = at(0xC400, &[0xE3, 0x45], (0xAA, 0x02, 0x4E, 0xEF, 0xF5), 12345);
+	ram.mem[0x47] = 0x47;
+	ram.mem[0x48] = 0x06;
+	ram.mem[0x0647] = 0xA5;
+	assert_eq!(
+		cpu.trace_line(&ram),
+		"C400  E3 45    *ISB ($45,X) @ 47 = 0647 = A5    A:AA X:02 Y:4E P:EF SP:F5 PPU:108,207 CYC:12345",
+	);
+}
@@ -0,0 +1,224 @@
+// Results of the unofficial opcodes
+//	https://www.nesdev.org/wiki/CPU_unofficial_opcodes
+//	https://www.nesdev.org/6502_cpu.txt
+//
+// Every case runs in both cores.
+
+
+mod common;
+
+use common::each_mode;
+use nes::bus::FlatRam;
+use nes::cpu::{Cpu, Status, StepMode};
+
+// Runs the instruction in bytes from $0400 after setup, then checks the result
+fn check(bytes: &[u8], setup: impl Fn(&mut Cpu, &mut FlatRam), check: impl Fn(StepMode, &Cpu, &FlatRam)) {
+	each_mode(|mode| {
+		let mut ram = FlatRam::init();
+		ram.mem[0x0400..0x0400 + bytes.len()].copy_from_slice(bytes);
+
+		let mut cpu = Cpu::init();
+		cpu.mode = mode;
+		cpu.pc = 0x0400;
+		setup(&mut cpu, &mut ram);
+
+		cpu.step_instruction(&mut ram);
+		check(mode, &cpu, &ram);
+	});
+}
+
+// N, V, Z and C
+fn flags(cpu: &Cpu) -> (bool, bool, bool, bool) {
+	let sr = cpu.sr;
+	(sr.contains(Status::N), sr.contains(Status::V), sr.contains(Status::Z), sr.contains(Status::C))
+}
+
+
+//
+// Combined Operations
+
+#[test]
+fn lax_and_sax() {
+	check(&[0xA7, 0x20], |_, ram| ram.mem[0x20] = 0x80, |mode, cpu, _| {
+		assert_eq!((cpu.ac, cpu.x), (0x80, 0x80), "LAX {:?}", mode);
+		assert_eq!(flags(cpu), (true, false, false, false), "LAX {:?}", mode);
+	});
+
+	// Stores A & X without touching the flags
+	let setup = |cpu: &mut Cpu, _: &mut FlatRam| {
+		cpu.ac = 0xF0;
+		cpu.x = 0x3C;
+		cpu.sr.insert(Status::Z);
+	};
+	check(&[0x87, 0x20], setup, |mode, cpu, ram| {
+		assert_eq!(ram.mem[0x20], 0x30, "SAX {:?}", mode);
+		assert_eq!((cpu.ac, cpu.x), (0xF0, 0x3C), "SAX {:?}", mode);
+		assert_eq!(flags(cpu), (false, false, true, false), "SAX {:?}", mode);
+	});
+}
+
+#[test]
+fn dcp_and_isc() {
+	// DEC then CMP
+	let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+		cpu.ac = 0x40;
+		ram.mem[0x20] = 0x41;
+	};
+	check(&[0xC7, 0x20], setup, |mode, cpu, ram| {
+		assert_eq!((ram.mem[0x20], cpu.ac), (0x40, 0x40), "DCP {:?}", mode);
+		assert_eq!(flags(cpu), (false, false, true, true), "DCP {:?}", mode);
+	});
+
+	// INC then SBC
+	let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+		cpu.ac = 0x20;
+		cpu.sr.insert(Status::C);
+		ram.mem[0x20] = 0x0F;
+	};
+	check(&[0xE7, 0x20], setup, |mode, cpu, ram| {
+		assert_eq!((ram.mem[0x20], cpu.ac), (0x10, 0x10), "ISC {:?}", mode);
+		assert_eq!(flags(cpu), (false, false, false, true), "ISC {:?}", mode);
+	});
+}
+
+#[test]
+fn shift_then_combine() {
+	// opcode, A, C, memory before -> memory, A, flags after
+	for (opcode, ac, carry, before, after, result, expected) in [
+		(0x07, 0x01, false, 0x81, 0x02, 0x03, (false, false, false, true)),	// SLO: ASL then ORA
+		(0x27, 0xFF, true, 0x81, 0x03, 0x03, (false, false, false, true)),		// RLA: ROL then AND
+		(0x47, 0xFF, false, 0x03, 0x01, 0xFE, (true, false, false, true)),		// SRE: LSR then EOR
+		(0x67, 0x10, false, 0x03, 0x01, 0x12, (false, false, false, false)),	// RRA: ROR then ADC with its carry
+	] {
+		let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+			cpu.ac = ac;
+			cpu.sr.set(Status::C, carry);
+			ram.mem[0x20] = before;
+		};
+		check(&[opcode, 0x20], setup, |mode, cpu, ram| {
+			assert_eq!((ram.mem[0x20], cpu.ac), (after, result), "{:02X} {:?}", opcode, mode);
+			assert_eq!(flags(cpu), expected, "{:02X} {:?}", opcode, mode);
+		});
+	}
+}
+
+
+//
+// Immediate
+
+#[test]
+fn anc_and_alr() {
+	// AND, then C takes N
+	for opcode in [0x0B, 0x2B] {
+		check(&[opcode, 0x80], |cpu, _| cpu.ac = 0xFF, |mode, cpu, _| {
+			assert_eq!(cpu.ac, 0x80, "{:02X} {:?}", opcode, mode);
+			assert_eq!(flags(cpu), (true, false, false, true), "{:02X} {:?}", opcode, mode);
+		});
+	}
+
+	// AND then LSR
+	check(&[0x4B, 0x03], |cpu, _| cpu.ac = 0xFF, |mode, cpu, _| {
+		assert_eq!(cpu.ac, 0x01, "ALR {:?}", mode);
+		assert_eq!(flags(cpu), (false, false, false, true), "ALR {:?}", mode);
+	});
+}
+
+#[test]
+fn arr() {
+	// AND then ROR, with C from bit 6 of the result and V from bit 6 ^ bit 5
+	for (operand, carry, result, expected) in [
+		(0xFF, true, 0xFF, (true, false, false, true)),
+		(0xC0, false, 0x60, (false, false, false, true)),
+		(0x80, false, 0x40, (false, true, false, true)),
+		(0x40, false, 0x20, (false, true, false, false)),
+		(0x01, false, 0x00, (false, false, true, false)),
+	] {
+		let setup = |cpu: &mut Cpu, _: &mut FlatRam| {
+			cpu.ac = 0xFF;
+			cpu.sr.set(Status::C, carry);
+		};
+		check(&[0x6B, operand], setup, |mode, cpu, _| {
+			assert_eq!(cpu.ac, result, "#${:02X} C={} {:?}", operand, carry, mode);
+			assert_eq!(flags(cpu), expected, "#${:02X} C={} {:?}", operand, carry, mode);
+		});
+	}
+}
+
+#[test]
+fn axs() {
+	// X = (A & X) - operand, with C set as by CMP and no borrow in
+	for (operand, x, expected) in [(0x10, 0x20, (false, false, false, true)), (0x40, 0xF0, (true, false, false, false))] {
+		let setup = |cpu: &mut Cpu, _: &mut FlatRam| {
+			cpu.ac = 0xF0;
+			cpu.x = 0x3C;
+		};
+		check(&[0xCB, operand], setup, |mode, cpu, _| {
+			assert_eq!((cpu.x, cpu.ac), (x, 0xF0), "#${:02X} {:?}", operand, mode);
+			assert_eq!(flags(cpu), expected, "#${:02X} {:?}", operand, mode);
+		});
+	}
+}
+
+
+//
+// Stack Pointer and Unstable Stores
+
+#[test]
+fn las() {
+	let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+		cpu.sp = 0x5F;
+		ram.mem[0x0300] = 0xF3;
+	};
+	check(&[0xBB, 0x00, 0x03], setup, |mode, cpu, _| {
+		assert_eq!((cpu.ac, cpu.x, cpu.sp), (0x53, 0x53, 0x53), "{:?}", mode);
+		assert_eq!(flags(cpu), (false, false, false, false), "{:?}", mode);
+	});
+}
+
+#[test]
+fn high_byte_and() {
+	// Base $0210 + 1: the value is ANDed with $02 + 1
+	for (name, bytes, a, x, y) in [
+		("SHA abs,Y", [0x9F, 0x10, 0x02], 0xFF, 0x07, 0x01),
+		("SHX", [0x9E, 0x10, 0x02], 0x00, 0xFF, 0x01),
+		("SHY", [0x9C, 0x10, 0x02], 0x00, 0x01, 0xFF),
+		("TAS", [0x9B, 0x10, 0x02], 0xF7, 0x3F, 0x01),
+	] {
+		let setup = |cpu: &mut Cpu, _: &mut FlatRam| {
+			cpu.ac = a;
+			cpu.x = x;
+			cpu.y = y;
+		};
+		check(&bytes, setup, |mode, cpu, ram| {
+			assert_eq!(ram.mem[0x0211], 0x03, "{} {:?}", name, mode);
+			if name == "TAS" {
+				assert_eq!(cpu.sp, 0x37, "{} {:?}", name, mode);
+			}
+		});
+	}
+}
+
+#[test]
+fn high_byte_and_page_cross() {
+	// Base $02F0 + $20 crosses into $0310, but the stored value $05 & $03
+	// also replaces the high byte of the address
+	for (name, bytes, a, x, y) in [
+		("SHA abs,Y", &[0x9F, 0xF0, 0x02][..], 0xFF, 0x05, 0x20),
+		("SHA (zp),Y", &[0x93, 0x40], 0xFF, 0x05, 0x20),
+		("SHX", &[0x9E, 0xF0, 0x02], 0x00, 0x05, 0x20),
+		("SHY", &[0x9C, 0xF0, 0x02], 0x00, 0x20, 0x05),
+		("TAS", &[0x9B, 0xF0, 0x02], 0x0F, 0x35, 0x20),
+	] {
+		let setup = |cpu: &mut Cpu, ram: &mut FlatRam| {
+			cpu.ac = a;
+			cpu.x = x;
+			cpu.y = y;
+			ram.mem[0x40] = 0xF0;
+			ram.mem[0x41] = 0x02;
+		};
+		check(bytes, setup, |mode, _, ram| {
+			assert_eq!(ram.mem[0x0110], 0x01, "{} {:?}", name, mode);
+			assert_eq!(ram.mem[0x0310], 0x00, "{} {:?}", name, mode);
+		});
+	}
+}